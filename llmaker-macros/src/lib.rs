@@ -0,0 +1,245 @@
+use llmaker::backend::snippet;
+use llmaker::{backend, error, frontend, types};
+use proc_macro::{Delimiter, Literal, Span, TokenStream, TokenTree};
+use std::path::Path;
+
+/// 文法をRustのコードの中に直接書き、その場でパーサを生成する
+///
+/// ```
+/// mod parser {
+///   llmaker_macros::grammar! {
+///     "type Token = char;"
+///     grammar;
+///     extern {
+///       enum "Token" {
+///         Tok_A => "'a'",
+///         Tok_B => "'b'",
+///       }
+///     }
+///     pub main: "usize" = {
+///       <_a: Tok_A> <rest: main> => {"rest + 1"},
+///       <_b: Tok_B> => {"0"},
+///     };
+///   }
+/// }
+///
+/// fn main() {
+///   assert_eq!(parser::parse(vec!['a', 'a', 'b']).unwrap(), 2);
+/// }
+/// ```
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+  let (contents, span_lst) = token_stream_to_string(input);
+  match generate("grammar!", &contents) {
+    Ok(output_str) => parse_output(&output_str, Span::call_site()).unwrap_or_else(|err| err),
+    Err(e) => {
+      let span = find_span(&e, &span_lst);
+      let msg = error::make_error_msg(e, Some("grammar!"), Some(&contents));
+      make_compile_error(&msg, span)
+    }
+  }
+}
+
+/// 文法ファイルから生成したパーサをモジュールの中身にする
+///
+/// ファイルの位置は`Cargo.toml`のあるディレクトリからの相対パスで指定する。
+///
+/// ```ignore
+/// #[llmaker_macros::llmaker("src/parser.mkr")]
+/// mod parser {}
+/// ```
+///
+/// モジュールの中に書かれた要素は、生成されたコードの後ろにそのまま残る。
+#[proc_macro_attribute]
+pub fn llmaker(attr: TokenStream, item: TokenStream) -> TokenStream {
+  let attr_span = match attr.clone().into_iter().next() {
+    Some(tt) => tt.span(),
+    None => Span::call_site(),
+  };
+  let input_file_name = match get_string_literal(attr) {
+    Some(s) => s,
+    None => {
+      return make_compile_error(
+        "expected a grammar file name such as `#[llmaker(\"parser.mkr\")]`",
+        attr_span,
+      )
+    }
+  };
+  let (head_tokens, mod_name, mod_body) = match split_mod_item(item) {
+    Some(v) => v,
+    None => {
+      return make_compile_error(
+        "`#[llmaker]` can only be used on `mod name { ... }`",
+        Span::call_site(),
+      )
+    }
+  };
+  let input_path = match std::env::var_os("CARGO_MANIFEST_DIR") {
+    Some(dir) => Path::new(&dir).join(&input_file_name),
+    None => Path::new(&input_file_name).to_path_buf(),
+  };
+  let contents = match std::fs::read_to_string(&input_path) {
+    Ok(contents) => contents,
+    Err(_) => {
+      let msg = error::make_error_msg(
+        error::Error::OptionError(error::OptionError::NotFoundInputFileName(
+          input_path.display().to_string(),
+        )),
+        None,
+        None,
+      );
+      return make_compile_error(&msg, attr_span);
+    }
+  };
+  let output = match generate(&input_file_name, &contents) {
+    Ok(output_str) => match parse_output(&output_str, attr_span) {
+      Ok(output) => output,
+      Err(err) => return err,
+    },
+    Err(e) => {
+      let msg = error::make_error_msg(e, Some(&input_file_name), Some(&contents));
+      return make_compile_error(&msg, attr_span);
+    }
+  };
+  // 文法ファイルが変更されたときに再コンパイルされるようにする
+  let tracking = format!(
+    "const _: &str = include_str!({});",
+    Literal::string(&input_path.display().to_string())
+  );
+  let mut body = tracking.parse::<TokenStream>().unwrap();
+  body.extend(output);
+  body.extend(mod_body);
+  let mut ts = head_tokens;
+  ts.extend(vec![
+    TokenTree::Ident(mod_name),
+    TokenTree::Group(proc_macro::Group::new(Delimiter::Brace, body)),
+  ]);
+  ts
+}
+
+fn generate(input_file_name: &str, contents: &str) -> Result<String, error::Error> {
+  let ast = frontend::get_ast(contents)?;
+  backend::to_string(ast, input_file_name, contents)
+}
+
+// 生成したコードをTokenStreamにする
+// synでの検査を切っていると、アクションの括弧の対応が取れていないコードがここまで来る
+// 括弧の対応が取れていない文字列をparseするとコンパイラがpanicすることがあるので、先に確認してcompile_error!を返す
+fn parse_output(output_str: &str, span: Span) -> Result<TokenStream, TokenStream> {
+  let res = snippet::check_delimiters(output_str)
+    .and_then(|()| output_str.parse::<TokenStream>().map_err(|e| e.to_string()));
+  res.map_err(|msg| {
+    make_compile_error(
+      &format!(
+        "![config file error]\n  invalid code in the generated parser\n  {}",
+        msg
+      ),
+      span,
+    )
+  })
+}
+
+// TokenStreamを文法の文字列に戻し、各トークンの位置とSpanの対応を記録する
+fn token_stream_to_string(input: TokenStream) -> (String, Vec<(usize, usize, Span)>) {
+  fn push(s: &mut String, span_lst: &mut Vec<(usize, usize, Span)>, text: &str, span: Span) {
+    let start = s.len();
+    s.push_str(text);
+    span_lst.push((start, s.len(), span));
+  }
+  fn go(input: TokenStream, s: &mut String, span_lst: &mut Vec<(usize, usize, Span)>) {
+    for tt in input.into_iter() {
+      match tt {
+        TokenTree::Group(group) => {
+          let (open, close) = match group.delimiter() {
+            Delimiter::Brace => ("{", "}"),
+            Delimiter::Bracket => ("[", "]"),
+            Delimiter::Parenthesis => ("(", ")"),
+            Delimiter::None => ("", ""),
+          };
+          push(s, span_lst, open, group.span_open());
+          s.push(' ');
+          go(group.stream(), s, span_lst);
+          push(s, span_lst, close, group.span_close());
+        }
+        TokenTree::Punct(punct) => {
+          push(s, span_lst, &punct.as_char().to_string(), punct.span());
+          if punct.spacing() == proc_macro::Spacing::Joint {
+            continue;
+          }
+        }
+        TokenTree::Ident(ident) => push(s, span_lst, &ident.to_string(), ident.span()),
+        TokenTree::Literal(literal) => push(s, span_lst, &literal.to_string(), literal.span()),
+      }
+      s.push(' ');
+    }
+  }
+  let mut s = String::new();
+  let mut span_lst = Vec::new();
+  go(input, &mut s, &mut span_lst);
+  (s, span_lst)
+}
+
+fn find_span(err: &error::Error, span_lst: &[(usize, usize, Span)]) -> Span {
+  let start_opt = error::get_error_range(err).map(|rng: types::Range| rng.to_tuple().0);
+  let span_opt = start_opt.and_then(|pos| {
+    span_lst
+      .iter()
+      .find(|(start, end, _)| *start <= pos && pos < *end)
+      .map(|(_, _, span)| *span)
+  });
+  span_opt.unwrap_or_else(Span::call_site)
+}
+
+fn get_string_literal(attr: TokenStream) -> Option<String> {
+  let mut iter = attr.into_iter();
+  let literal = match (iter.next(), iter.next()) {
+    (Some(TokenTree::Literal(literal)), None) => literal,
+    _ => return None,
+  };
+  let s = literal.to_string();
+  if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+    Some(s[1..s.len() - 1].to_string())
+  } else {
+    None
+  }
+}
+
+// `pub mod name { ... }`を、`mod`までのトークンとモジュール名とモジュールの中身に分ける
+// （`mod name;`の形は属性マクロに渡すことができない）
+fn split_mod_item(item: TokenStream) -> Option<(TokenStream, proc_macro::Ident, TokenStream)> {
+  let tts: Vec<TokenTree> = item.into_iter().collect();
+  let mod_pos = tts
+    .iter()
+    .position(|tt| matches!(tt, TokenTree::Ident(ident) if ident.to_string() == "mod"))?;
+  match (tts.get(mod_pos + 1), tts.get(mod_pos + 2), tts.len()) {
+    (Some(TokenTree::Ident(name)), Some(TokenTree::Group(group)), len)
+      if group.delimiter() == Delimiter::Brace && len == mod_pos + 3 =>
+    {
+      let head_tokens = tts[..=mod_pos].iter().cloned().collect();
+      Some((head_tokens, name.clone(), group.stream()))
+    }
+    _ => None,
+  }
+}
+
+fn make_compile_error(msg: &str, span: Span) -> TokenStream {
+  let mut ts: TokenStream = "compile_error!".parse().unwrap();
+  let mut literal = Literal::string(msg);
+  literal.set_span(span);
+  let mut group = proc_macro::Group::new(
+    Delimiter::Parenthesis,
+    TokenStream::from(TokenTree::Literal(literal)),
+  );
+  group.set_span(span);
+  ts.extend(vec![
+    TokenTree::Group(group),
+    TokenTree::Punct(proc_macro::Punct::new(';', proc_macro::Spacing::Alone)),
+  ]);
+  // compile_error!のSpanもエラーの位置にする
+  ts.into_iter()
+    .map(|mut tt| {
+      tt.set_span(span);
+      tt
+    })
+    .collect()
+}
@@ -0,0 +1,69 @@
+use llmaker::backend::{interpreter, sample};
+use llmaker::frontend;
+
+#[llmaker_macros::llmaker("tests/expr.mkr")]
+mod parser {}
+
+fn to_token_name(c: char) -> String {
+  match c {
+    '1' => "Tok_NUM",
+    '+' => "Tok_PLUS",
+    '(' => "Tok_LPAREN",
+    ')' => "Tok_RPAREN",
+    ';' => "Tok_SEMI",
+    _ => unreachable!(),
+  }
+  .to_string()
+}
+
+fn to_char(tok_name: &str) -> char {
+  "1+();"
+    .chars()
+    .find(|c| to_token_name(*c) == tok_name)
+    .unwrap()
+}
+
+// 生成されたパーサとインタプリタが、入力の終わりで空になりうる規則を含めて同じ入力を受け付けるか
+#[test]
+fn check_interpreter_agrees_with_parser() {
+  let term = frontend::get_ast(include_str!("expr.mkr")).unwrap();
+  for input in [
+    "", "1", "1;", "1+", "1+1", "(1)+1", "(1)+1;", "(1", "1)", "+", "1;;", ";",
+  ]
+  .iter()
+  {
+    let tokens: Vec<String> = input.chars().map(to_token_name).collect();
+    let is_parsed = parser::parse(input.chars().collect()).is_ok();
+    let is_interpreted = interpreter::parse(&term, "stmt", &tokens).is_ok();
+    assert_eq!(is_parsed, is_interpreted, "input: {:?}", input);
+  }
+  assert_eq!(parser::parse("1".chars().collect()).ok(), Some(1));
+  assert_eq!(parser::parse("(1)+1".chars().collect()).ok(), Some(2));
+}
+
+// generate-samplesの列は、生成されたパーサでも読める（near-missなら読めない）
+#[test]
+fn check_samples_agree_with_parser() {
+  let term = frontend::get_ast(include_str!("expr.mkr")).unwrap();
+  let mut options = sample::SampleOptions {
+    count: 50,
+    max_depth: 6,
+    seed: 1,
+    is_near_miss: false,
+  };
+  for is_near_miss in [false, true].iter() {
+    options.is_near_miss = *is_near_miss;
+    for tokens in sample::generate_samples(&term, "stmt", &options)
+      .unwrap()
+      .iter()
+    {
+      let input: Vec<char> = tokens.iter().map(|tok_name| to_char(tok_name)).collect();
+      assert_eq!(
+        parser::parse(input).is_ok(),
+        !is_near_miss,
+        "tokens: {:?}",
+        tokens
+      );
+    }
+  }
+}
@@ -1,16 +1,107 @@
 use super::error;
 use super::types;
+pub mod analysis;
+pub mod ast_json;
+pub mod ast_type;
+pub mod coverage;
+pub mod cst;
+pub mod dot;
+pub mod grammar_test;
 pub mod headstr;
+pub mod interpreter;
 pub mod parse_fn;
 pub mod parse_token;
+pub mod railroad;
+pub mod sample;
+pub mod snippet;
+pub mod source_map;
+pub mod symbols;
+pub mod tables;
+pub mod trace;
+pub mod visitor;
 
-pub fn to_string(term: types::Term) -> Result<String, error::Error> {
+/// パーサのコードを生成するときの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GenerateOptions {
+  /// 各規則の各選択肢が選ばれた回数を数え、`coverage_counts`と`coverage_dump`で取り出せるようにする
+  pub is_coverage: bool,
+  /// 規則とトークンを読み始めたとき・読み終えたとき・選択肢を選んだときに`ParseTracer`へイベントを送り、
+  /// `parse_with_tracer`で`ParseTracer`を渡せるようにする
+  pub is_trace: bool,
+  /// アクションを使わずに、規則ごとのvariantを持つ`Node`の具象構文木を返すパーサを生成する
+  pub is_cst: bool,
+}
+
+/// 左再帰と、同じトークンで選ばれる選択肢が無いかを調べる
+///
+/// これらがあってもパーサは生成できるが、生成されたパーサは止まらなくなったり、
+/// 後ろの選択肢を選べなくなったりする。
+pub fn check_analysis(term: &types::Term) -> Result<(), error::Error> {
+  let (_, _, bnfs) = term;
+  analysis::check_left_recursion(bnfs)?;
+  analysis::check_conflicts(bnfs)
+}
+
+pub fn to_string(
+  term: types::Term,
+  input_file_name: &str,
+  input: &str,
+) -> Result<String, error::Error> {
+  to_string_with_options(term, input_file_name, input, &GenerateOptions::default())
+}
+
+pub fn to_string_with_options(
+  term: types::Term,
+  input_file_name: &str,
+  input: &str,
+  options: &GenerateOptions,
+) -> Result<String, error::Error> {
+  let (term, ast_type_str) = if options.is_cst {
+    cst::check_type_names(&term.2, &cst::RESERVED_TYPE_NAMES)?;
+    (cst::make_cst_term(&term), String::new())
+  } else {
+    ast_type::check_auto_rules(&term.2)?;
+    let ast_type_lst = ast_type::make_ast_type_lst(&term);
+    let ast_type_str = if ast_type_lst.is_empty() {
+      String::new()
+    } else {
+      let ((_, main_type_str), _) = &term.1;
+      format!(
+        "{}{}",
+        ast_type::make_ast_type_str(&ast_type_lst),
+        visitor::make_ast_visitor_str(&ast_type_lst, main_type_str)
+      )
+    };
+    (ast_type::make_ast_term(&term, &ast_type_lst), ast_type_str)
+  };
   let (head, setting, bnfs) = term;
+  snippet::check_snippets(&setting, &bnfs)?;
   let head_str = headstr::head_to_str(head, setting.clone());
-  let parse_token_fn_str = parse_token::make_parse_token_fn_str(setting.clone());
-  let parse_fn_fn_str = parse_fn::make_parse_fn_fn_str(setting, &bnfs)?;
+  let parse_token_fn_str = parse_token::make_parse_token_fn_str(setting.clone(), options);
+  let node_type_str = if options.is_cst {
+    let ((_, main_type_str), _) = &setting;
+    format!(
+      "{}{}",
+      cst::make_node_type_str(&bnfs, main_type_str),
+      visitor::make_cst_visitor_str(&bnfs, main_type_str)
+    )
+  } else {
+    ast_type_str
+  };
+  let parse_fn_fn_str =
+    parse_fn::make_parse_fn_fn_str(setting, &bnfs, input_file_name, input.as_bytes(), options)?;
+  let coverage_fn_str = if options.is_coverage {
+    coverage::make_coverage_fn_str(&bnfs, input_file_name)
+  } else {
+    String::new()
+  };
+  let tracer_str = if options.is_trace {
+    trace::make_tracer_str()
+  } else {
+    String::new()
+  };
   Ok(format!(
-    "{}\n{}\n{}\n",
-    head_str, parse_fn_fn_str, parse_token_fn_str
+    "{}\n{}\n{}\n{}{}{}",
+    head_str, parse_fn_fn_str, parse_token_fn_str, node_type_str, coverage_fn_str, tracer_str
   ))
 }
@@ -0,0 +1,412 @@
+use super::error;
+use super::types;
+use std::collections::{BTreeSet, HashMap};
+
+// 文法の解析
+// 空になりうる規則（nullable）、各規則の先頭に来うるトークンの集合（FIRST）、
+// 各規則の直後に来うるトークンの集合（FOLLOW）を求め、
+// そこから各選択肢を選ぶトークンの集合（predict）を求める。
+// 生成されるコードはpredictを使って選択肢を決め、
+// 空の選択肢はどの選択肢にも当てはまらなかったときに使われる。
+
+// 入力の終わりを表す記号（FOLLOWとpredictにだけ現れる）
+pub const END_OF_INPUT: &str = "$";
+
+fn make_code_lst_map(bnfs: &[types::Bnf]) -> HashMap<&String, &Vec<types::Code>> {
+  let mut code_lst_map = HashMap::new();
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    code_lst_map.insert(name, code_lst);
+  }
+  code_lst_map
+}
+
+// 空の列を導出できる規則の集合を求める
+pub fn make_nullable_set(bnfs: &[types::Bnf]) -> BTreeSet<String> {
+  let code_lst_map = make_code_lst_map(bnfs);
+  let mut nullable_set = BTreeSet::new();
+  // 変化が無くなるまで繰り返す
+  let mut is_changed = true;
+  while is_changed {
+    is_changed = false;
+    for (name, code_lst) in code_lst_map.iter() {
+      if nullable_set.contains(*name) {
+        continue;
+      }
+      let is_nullable = code_lst.iter().any(|(fn_or_token_lst, _)| {
+        fn_or_token_lst
+          .iter()
+          .all(|(_, fn_or_token)| is_nullable_symbol(fn_or_token, &nullable_set))
+      });
+      if is_nullable {
+        is_changed = true;
+        nullable_set.insert(name.to_string());
+      }
+    }
+  }
+  nullable_set
+}
+
+fn is_nullable_symbol(fn_or_token: &types::FnOrToken, nullable_set: &BTreeSet<String>) -> bool {
+  match fn_or_token {
+    types::FnOrToken::Token(_, _) => false,
+    types::FnOrToken::Function(_, fn_name) => nullable_set.contains(fn_name),
+  }
+}
+
+// 各規則の先頭に来うるトークンの集合を求める
+pub fn make_first_set_map(bnfs: &[types::Bnf]) -> HashMap<String, BTreeSet<String>> {
+  let code_lst_map = make_code_lst_map(bnfs);
+  let nullable_set = make_nullable_set(bnfs);
+  let mut first_set_map: HashMap<String, BTreeSet<String>> = HashMap::new();
+  for name in code_lst_map.keys() {
+    first_set_map.insert(name.to_string(), BTreeSet::new());
+  }
+  // 変化が無くなるまで繰り返す
+  let mut is_changed = true;
+  while is_changed {
+    is_changed = false;
+    for (name, code_lst) in code_lst_map.iter() {
+      let mut new_set = first_set_map[*name].clone();
+      for (fn_or_token_lst, _) in code_lst.iter() {
+        let (set, _) = get_seq_first_set(fn_or_token_lst, &first_set_map, &nullable_set);
+        new_set.extend(set);
+      }
+      if new_set.len() != first_set_map[*name].len() {
+        is_changed = true;
+        first_set_map.insert(name.to_string(), new_set);
+      }
+    }
+  }
+  first_set_map
+}
+
+// 記号の列の先頭に来うるトークンの集合と、その列が空になりうるかどうか
+pub fn get_seq_first_set(
+  fn_or_token_lst: &[(String, types::FnOrToken)],
+  first_set_map: &HashMap<String, BTreeSet<String>>,
+  nullable_set: &BTreeSet<String>,
+) -> (BTreeSet<String>, bool) {
+  let mut set = BTreeSet::new();
+  for (_, fn_or_token) in fn_or_token_lst.iter() {
+    set.extend(get_first_set(fn_or_token, first_set_map));
+    if !is_nullable_symbol(fn_or_token, nullable_set) {
+      return (set, false);
+    }
+  }
+  (set, true)
+}
+
+// 各規則の直後に来うるトークンの集合を求める
+// 最初のpubな規則の後には入力の終わり（END_OF_INPUT）が来る
+pub fn make_follow_set_map(
+  bnfs: &[types::Bnf],
+  first_set_map: &HashMap<String, BTreeSet<String>>,
+  nullable_set: &BTreeSet<String>,
+) -> HashMap<String, BTreeSet<String>> {
+  let code_lst_map = make_code_lst_map(bnfs);
+  let mut follow_set_map: HashMap<String, BTreeSet<String>> = HashMap::new();
+  for name in code_lst_map.keys() {
+    follow_set_map.insert(name.to_string(), BTreeSet::new());
+  }
+  if let Some(types::Bnf::Pub(_, name, _, _)) = bnfs
+    .iter()
+    .find(|bnf| matches!(bnf, types::Bnf::Pub(_, _, _, _)))
+  {
+    if let Some(set) = follow_set_map.get_mut(name) {
+      set.insert(END_OF_INPUT.to_string());
+    }
+  }
+  // 変化が無くなるまで繰り返す
+  let mut is_changed = true;
+  while is_changed {
+    is_changed = false;
+    for (name, code_lst) in code_lst_map.iter() {
+      for (fn_or_token_lst, _) in code_lst.iter() {
+        for (i, (_, fn_or_token)) in fn_or_token_lst.iter().enumerate() {
+          let fn_name = match fn_or_token {
+            types::FnOrToken::Function(_, fn_name) => fn_name,
+            types::FnOrToken::Token(_, _) => continue,
+          };
+          let (mut new_set, is_rest_nullable) =
+            get_seq_first_set(&fn_or_token_lst[i + 1..], first_set_map, nullable_set);
+          if is_rest_nullable {
+            new_set.extend(follow_set_map[*name].iter().cloned());
+          }
+          if let Some(set) = follow_set_map.get_mut(fn_name) {
+            let old_len = set.len();
+            set.extend(new_set);
+            if set.len() != old_len {
+              is_changed = true;
+            }
+          }
+        }
+      }
+    }
+  }
+  follow_set_map
+}
+
+// 各規則の各選択肢を選ぶトークンの集合を求める
+// 空になりうる選択肢は、その規則の直後に来うるトークンでも選ばれる
+pub fn make_predict_set_map(bnfs: &[types::Bnf]) -> HashMap<String, Vec<BTreeSet<String>>> {
+  let nullable_set = make_nullable_set(bnfs);
+  let first_set_map = make_first_set_map(bnfs);
+  let follow_set_map = make_follow_set_map(bnfs, &first_set_map, &nullable_set);
+  let mut predict_set_map = HashMap::new();
+  for (name, code_lst) in make_code_lst_map(bnfs).iter() {
+    let predict_set_lst = code_lst
+      .iter()
+      .map(|(fn_or_token_lst, _)| {
+        let (mut set, is_nullable) =
+          get_seq_first_set(fn_or_token_lst, &first_set_map, &nullable_set);
+        if is_nullable {
+          set.extend(follow_set_map[*name].iter().cloned());
+        }
+        set
+      })
+      .collect();
+    predict_set_map.insert(name.to_string(), predict_set_lst);
+  }
+  predict_set_map
+}
+
+// 記号の先頭に来うるトークンの集合
+pub fn get_first_set(
+  fn_or_token: &types::FnOrToken,
+  first_set_map: &HashMap<String, BTreeSet<String>>,
+) -> BTreeSet<String> {
+  match fn_or_token {
+    types::FnOrToken::Token(_, tok_name) => {
+      let mut set = BTreeSet::new();
+      set.insert(tok_name.clone());
+      set
+    }
+    types::FnOrToken::Function(_, fn_name) => match first_set_map.get(fn_name) {
+      Some(set) => set.clone(),
+      None => BTreeSet::new(),
+    },
+  }
+}
+
+// 規則の先頭を辿って同じ規則に戻ってくるものが無いか確認する
+// 空になりうる記号は読み飛ばして、その次の記号も先頭として扱う
+pub fn check_left_recursion(bnfs: &[types::Bnf]) -> Result<(), error::Error> {
+  let code_lst_map = make_code_lst_map(bnfs);
+  let nullable_set = make_nullable_set(bnfs);
+  for bnf in bnfs.iter() {
+    let (rng, name) = match bnf {
+      types::Bnf::Pub(rng, name, _, _) => (rng, name),
+      types::Bnf::NonPub(rng, name, _, _) => (rng, name),
+    };
+    if let Some(path) = find_left_recursion(name, &code_lst_map, &nullable_set) {
+      return Err(error::Error::ConfigError(
+        error::ConfigError::LeftRecursion(*rng, path),
+      ));
+    }
+  }
+  Ok(())
+}
+
+// nameから先頭の記号を辿ってnameに戻ってくる経路を探す
+fn find_left_recursion(
+  name: &str,
+  code_lst_map: &HashMap<&String, &Vec<types::Code>>,
+  nullable_set: &BTreeSet<String>,
+) -> Option<Vec<String>> {
+  let mut visited = BTreeSet::new();
+  let mut stack = vec![vec![name.to_string()]];
+  while let Some(path) = stack.pop() {
+    let last = path.last().unwrap();
+    let code_lst = match code_lst_map.get(last) {
+      Some(code_lst) => code_lst,
+      None => continue,
+    };
+    for (fn_or_token_lst, _) in code_lst.iter() {
+      for (_, fn_or_token) in fn_or_token_lst.iter() {
+        if let types::FnOrToken::Function(_, fn_name) = fn_or_token {
+          let mut new_path = path.clone();
+          new_path.push(fn_name.clone());
+          if fn_name == name {
+            return Some(new_path);
+          }
+          if visited.insert(fn_name.clone()) {
+            stack.push(new_path)
+          }
+        }
+        if !is_nullable_symbol(fn_or_token, nullable_set) {
+          break;
+        }
+      }
+    }
+  }
+  None
+}
+
+// 同じ規則の中で、同じトークンで選ばれる選択肢や空の選択肢が複数無いか確認する
+// 空の選択肢はその規則の直後に来うるトークンで選ばれるので、そのトークンで選ばれる他の選択肢とは衝突する
+// （生成されるパーサでは他の選択肢が優先されるので、空の選択肢は選ばれない）
+pub fn check_conflicts(bnfs: &[types::Bnf]) -> Result<(), error::Error> {
+  let predict_set_map = make_predict_set_map(bnfs);
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    let predict_set_lst = &predict_set_map[name];
+    let mut seen: Vec<(usize, bool, &BTreeSet<String>)> = Vec::new();
+    for (i, (fn_or_token_lst, (code_rng, _))) in code_lst.iter().enumerate() {
+      let is_empty = fn_or_token_lst.is_empty();
+      let predict_set = &predict_set_lst[i];
+      for (j, is_seen_empty, seen_set) in seen.iter() {
+        let token_opt = if *is_seen_empty && is_empty {
+          Some(None)
+        } else {
+          seen_set
+            .intersection(predict_set)
+            .next()
+            .map(|tok_name| Some(tok_name.clone()))
+        };
+        if let Some(token) = token_opt {
+          let err_rng = match fn_or_token_lst.first() {
+            Some((_, fn_or_token)) => fn_or_token.range(),
+            None => *code_rng,
+          };
+          return Err(error::Error::ConfigError(error::ConfigError::Conflict(
+            err_rng,
+            name.clone(),
+            (*j, i),
+            token,
+          )));
+        }
+      }
+      seen.push((i, is_empty, predict_set))
+    }
+  }
+  Ok(())
+}
+
+// 各規則の選択肢がどのトークンで選ばれるかを、--trace-analysisで表示するための文字列にする
+pub fn make_trace_str(bnfs: &[types::Bnf]) -> String {
+  let first_set_map = make_first_set_map(bnfs);
+  let predict_set_map = make_predict_set_map(bnfs);
+  let mut s = String::new();
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    s.push_str(&format!(
+      "rule `{}`: FIRST = {}\n",
+      name,
+      set_to_str(&first_set_map[name])
+    ));
+    for (i, (fn_or_token_lst, _)) in code_lst.iter().enumerate() {
+      let alternative_str = match fn_or_token_lst.first() {
+        Some((_, fn_or_token)) => format!(
+          "starts with `{}`, selected by {}",
+          fn_or_token.name(),
+          set_to_str(&predict_set_map[name][i])
+        ),
+        None => "empty, selected when no other alternative matches".to_string(),
+      };
+      s.push_str(&format!("  alternative {}: {}\n", i, alternative_str));
+    }
+  }
+  s
+}
+
+// `{Tok_A, Tok_B}`のような表示用の文字列にする
+pub fn set_to_str(set: &BTreeSet<String>) -> String {
+  let names: Vec<&str> = set.iter().map(|s| s.as_str()).collect();
+  format!("{{{}}}", names.join(", "))
+}
+
+#[test]
+fn check_analysis() {
+  let rng = types::Range::dummy();
+  let fn_ = |s: &str| {
+    (
+      s.to_string(),
+      types::FnOrToken::Function(rng, s.to_string()),
+    )
+  };
+  let tok = |s: &str| (s.to_string(), types::FnOrToken::Token(rng, s.to_string()));
+  let bnf = |name: &str, code_lst: Vec<Vec<(String, types::FnOrToken)>>| {
+    types::Bnf::NonPub(
+      rng,
+      name.to_string(),
+      (rng, "()".to_string()),
+      code_lst
+        .into_iter()
+        .map(|v| (v, (rng, String::new())))
+        .collect(),
+    )
+  };
+  let bnfs = vec![
+    bnf("a", vec![vec![fn_("b"), tok("Tok_X")], vec![tok("Tok_Y")]]),
+    bnf("b", vec![vec![tok("Tok_Z")], vec![]]),
+  ];
+  let nullable_set = make_nullable_set(&bnfs);
+  assert_eq!(nullable_set.iter().collect::<Vec<_>>(), vec!["b"]);
+  let first_set_map = make_first_set_map(&bnfs);
+  assert_eq!(
+    first_set_map["a"].iter().collect::<Vec<_>>(),
+    vec!["Tok_X", "Tok_Y", "Tok_Z"]
+  );
+  let follow_set_map = make_follow_set_map(&bnfs, &first_set_map, &nullable_set);
+  assert_eq!(
+    follow_set_map["b"].iter().collect::<Vec<_>>(),
+    vec!["Tok_X"]
+  );
+  assert!(check_left_recursion(&bnfs).is_ok());
+  assert!(check_conflicts(&bnfs).is_ok());
+  // 空の選択肢と、規則の直後に来うるトークンで始まる選択肢
+  let bnfs_with_empty_conflict = vec![
+    bnf("a", vec![vec![fn_("b"), tok("Tok_Z")]]),
+    bnf("b", vec![vec![tok("Tok_Z")], vec![]]),
+  ];
+  assert!(matches!(
+    check_conflicts(&bnfs_with_empty_conflict),
+    Err(error::Error::ConfigError(error::ConfigError::Conflict(
+      _,
+      _,
+      (0, 1),
+      Some(_)
+    )))
+  ));
+  assert!(make_trace_str(&bnfs)
+    .contains("  alternative 0: starts with `b`, selected by {Tok_X, Tok_Z}\n"));
+  let bnfs = vec![
+    bnf("a", vec![vec![fn_("b")], vec![tok("Tok_Z")]]),
+    bnf("b", vec![vec![tok("Tok_Z")], vec![], vec![]]),
+  ];
+  assert!(matches!(
+    check_conflicts(&bnfs),
+    Err(error::Error::ConfigError(error::ConfigError::Conflict(
+      _,
+      _,
+      (0, 1),
+      Some(_)
+    )))
+  ));
+  let bnfs = vec![
+    bnf("a", vec![vec![fn_("b")]]),
+    bnf("b", vec![vec![fn_("a"), tok("Tok_X")]]),
+  ];
+  assert!(matches!(
+    check_left_recursion(&bnfs),
+    Err(error::Error::ConfigError(
+      error::ConfigError::LeftRecursion(_, _)
+    ))
+  ));
+  // 空になりうる規則の後ろにある左再帰
+  let bnfs = vec![
+    bnf("a", vec![vec![fn_("b"), fn_("a")], vec![tok("Tok_X")]]),
+    bnf("b", vec![vec![tok("Tok_Z")], vec![]]),
+  ];
+  assert!(check_left_recursion(&bnfs).is_err());
+}
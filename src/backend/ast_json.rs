@@ -0,0 +1,195 @@
+use super::ast_type;
+use super::error;
+use super::source_map::escape_json_str;
+use super::types;
+
+/// 文法ファイルの構文木（`types::Term`）をJSONにする
+///
+/// `input`は位置情報から行番号と列番号を求めるために使う、文法ファイルの中身。
+/// 出力の形は次の通り（`RANGE`は位置情報）。
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "head": [ { "range": RANGE, "code": "..." } ],
+///   "setting": {
+///     "token_type": { "range": RANGE, "code": "..." },
+///     "tokens": [
+///       { "range": RANGE, "name": "Tok_A", "pattern": { "range": RANGE, "code": "..." } }
+///     ]
+///   },
+///   "rules": [
+///     {
+///       "range": RANGE, "name": "main", "pub": true,
+///       "type": { "range": RANGE, "code": "..." },
+///       "alternatives": [
+///         {
+///           "symbols": [
+///             { "binding": "a", "kind": "token" | "rule", "name": "Tok_A", "range": RANGE }
+///           ],
+///           "action": { "range": RANGE, "code": "..." }
+///         }
+///       ]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// 型を省略した規則では`type`と選択肢の`action`が`null`になり、選択肢に`=> Label`で付けたラベルを表す
+/// `"label": { "range": RANGE, "name": "Label" }`（ラベルが無ければ`null`）が加わる。
+///
+/// `RANGE`は`{ "start": 0, "end": 5, "line": 1, "column": 1, "end_line": 1, "end_column": 5 }`
+/// の形で、`start`と`end`はバイト単位の位置（`end`は含まない）、
+/// `line`と`column`は最初の文字、`end_line`と`end_column`は最後の文字の位置で、1から数える。
+/// 文字列の位置情報は引用符を含む。
+pub fn make_ast_json_str(term: &types::Term, input: &str) -> String {
+  let (head, ((token_type_rng, token_type), token_lst), bnfs) = term;
+  let input_bytes = input.as_bytes();
+  let range_str = |rng: &types::Range| make_range_str(rng, input_bytes);
+  let code_str = |rng: &types::Range, code: &str| {
+    format!(
+      "{{ \"range\": {}, \"code\": {} }}",
+      range_str(rng),
+      escape_json_str(code)
+    )
+  };
+  let head_str_lst: Vec<String> = head
+    .iter()
+    .map(|(rng, code)| format!("    {}", code_str(rng, code)))
+    .collect();
+  let token_str_lst: Vec<String> = token_lst
+    .iter()
+    .map(|(rng, name, (pattern_rng, pattern))| {
+      format!(
+        "      {{ \"range\": {}, \"name\": {}, \"pattern\": {} }}",
+        range_str(rng),
+        escape_json_str(name),
+        code_str(pattern_rng, pattern)
+      )
+    })
+    .collect();
+  let mut rule_str_lst = Vec::new();
+  for bnf in bnfs.iter() {
+    let (is_pub, rng, name, (type_rng, type_str), code_lst) = match bnf {
+      types::Bnf::Pub(rng, name, type_str, code_lst) => (true, rng, name, type_str, code_lst),
+      types::Bnf::NonPub(rng, name, type_str, code_lst) => (false, rng, name, type_str, code_lst),
+    };
+    let is_auto_rule = ast_type::is_auto_rule(bnf);
+    let mut alternative_str_lst = Vec::new();
+    for (fn_or_token_lst, (action_rng, action)) in code_lst.iter() {
+      let symbol_str_lst: Vec<String> = fn_or_token_lst
+        .iter()
+        .map(|(binding, fn_or_token)| {
+          let kind = match fn_or_token {
+            types::FnOrToken::Function(_, _) => "rule",
+            types::FnOrToken::Token(_, _) => "token",
+          };
+          format!(
+            "            {{ \"binding\": {}, \"kind\": \"{}\", \"name\": {}, \"range\": {} }}",
+            escape_json_str(binding),
+            kind,
+            escape_json_str(fn_or_token.name()),
+            range_str(&fn_or_token.range())
+          )
+        })
+        .collect();
+      let action_str = if is_auto_rule {
+        let label_str = if action.is_empty() {
+          "null".to_string()
+        } else {
+          format!(
+            "{{ \"range\": {}, \"name\": {} }}",
+            range_str(action_rng),
+            escape_json_str(action)
+          )
+        };
+        format!("null,\n          \"label\": {}", label_str)
+      } else {
+        code_str(action_rng, action)
+      };
+      alternative_str_lst.push(format!(
+        "        {{
+          \"symbols\": [{}],
+          \"action\": {}
+        }}",
+        join_lines(&symbol_str_lst, "          "),
+        action_str
+      ))
+    }
+    let type_json_str = if is_auto_rule {
+      "null".to_string()
+    } else {
+      code_str(type_rng, type_str)
+    };
+    rule_str_lst.push(format!(
+      "    {{
+      \"range\": {},
+      \"name\": {},
+      \"pub\": {},
+      \"type\": {},
+      \"alternatives\": [{}]
+    }}",
+      range_str(rng),
+      escape_json_str(name),
+      is_pub,
+      type_json_str,
+      join_lines(&alternative_str_lst, "      ")
+    ))
+  }
+  format!(
+    "{{
+  \"version\": 1,
+  \"head\": [{}],
+  \"setting\": {{
+    \"token_type\": {},
+    \"tokens\": [{}]
+  }},
+  \"rules\": [{}]
+}}
+",
+    join_lines(&head_str_lst, "  "),
+    code_str(token_type_rng, token_type),
+    join_lines(&token_str_lst, "    "),
+    join_lines(&rule_str_lst, "  ")
+  )
+}
+
+// 配列の要素を1行ずつ並べる（空なら`[]`になるようにする）
+fn join_lines(str_lst: &[String], indent: &str) -> String {
+  if str_lst.is_empty() {
+    String::new()
+  } else {
+    format!("\n{}\n{}", str_lst.join(",\n"), indent)
+  }
+}
+
+fn make_range_str(rng: &types::Range, input_bytes: &[u8]) -> String {
+  let (start, end) = rng.to_tuple();
+  let (_, (line, column), (end_line, end_column)) = error::get_error_point(*rng, input_bytes);
+  format!(
+    "{{ \"start\": {}, \"end\": {}, \"line\": {}, \"column\": {}, \"end_line\": {}, \"end_column\": {} }}",
+    start, end, line, column, end_line, end_column
+  )
+}
+
+#[test]
+fn check_make_ast_json_str() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let json_str = make_ast_json_str(&term, input);
+  assert!(json_str.contains("\"head\": [],"));
+  assert!(json_str.contains(
+    "{ \"binding\": \"a\", \"kind\": \"token\", \"name\": \"Tok_A\", \"range\": { \"start\": 86, \"end\": 91, \"line\": 8, \"column\": 7, \"end_line\": 8, \"end_column\": 11 } }"
+  ));
+  assert!(json_str.contains("\"symbols\": [],"));
+}
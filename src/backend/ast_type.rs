@@ -0,0 +1,472 @@
+use super::cst;
+use super::error;
+use super::types;
+use std::collections::{HashMap, HashSet};
+
+// 型を省略した規則から、構文木の型とその値を作るコードを生成する
+// 選択肢が1つでラベルが無い規則からは構造体を、それ以外の規則からはラベルをvariantの名前とする列挙型を作り、
+// `_`で始まらない変数に束縛した記号をフィールドにし、型が自分自身を含むときは一部のフィールドを`Box`に入れる
+// 生成した型とコードで型の文字列とアクションを置き換えた文法を作り、それを通常と同じように生成する
+
+/// 生成する型のフィールド
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+  /// フィールドの名前（記号を束縛した変数の名前）
+  pub name: String,
+  /// フィールドの型（`Box`に入れるときは中身の型）
+  pub type_str: String,
+  /// 参照する規則の型が自分の型を含みうるので、`Box`に入れるか
+  pub is_boxed: bool,
+  /// フィールドにする記号の種類
+  pub kind: FieldKind,
+}
+
+/// フィールドにする記号の種類
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+  Token,
+  /// 型を書いた規則
+  Rule(String),
+  /// 型を省略した規則
+  AutoRule(String),
+}
+
+/// 型を省略した規則から生成する型
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstType {
+  /// 型の名前・規則の名前・フィールドを持つ構造体
+  Struct(String, String, Vec<Field>),
+  /// 型の名前・規則の名前・variantの名前とフィールドの列を持つ列挙型
+  Enum(String, String, Vec<(String, Vec<Field>)>),
+}
+
+impl AstType {
+  pub fn type_name(&self) -> &String {
+    match self {
+      AstType::Struct(type_name, _, _) => type_name,
+      AstType::Enum(type_name, _, _) => type_name,
+    }
+  }
+  pub fn rule_name(&self) -> &String {
+    match self {
+      AstType::Struct(_, rule_name, _) => rule_name,
+      AstType::Enum(_, rule_name, _) => rule_name,
+    }
+  }
+}
+
+/// 型を省略した規則か
+pub fn is_auto_rule(bnf: &types::Bnf) -> bool {
+  match bnf {
+    types::Bnf::Pub(_, _, (_, type_str), _) | types::Bnf::NonPub(_, _, (_, type_str), _) => {
+      type_str.is_empty()
+    }
+  }
+}
+
+fn get_bnf(bnf: &types::Bnf) -> (&types::Range, &String, &types::TypeStr, &Vec<types::Code>) {
+  match bnf {
+    types::Bnf::Pub(rng, name, type_str, code_lst)
+    | types::Bnf::NonPub(rng, name, type_str, code_lst) => (rng, name, type_str, code_lst),
+  }
+}
+
+// 選択肢が1つでラベルが無ければ構造体にする
+fn is_struct(code_lst: &[types::Code]) -> bool {
+  match code_lst {
+    [(_, (_, label))] => label.is_empty(),
+    _ => false,
+  }
+}
+
+// `_`で始まる変数に束縛した記号はフィールドにしない
+fn is_field_name(name: &str) -> bool {
+  !name.starts_with('_')
+}
+
+// 型を省略した規則の型以外に、生成されるコードで使われている型やトレイトの名前
+// 生成されるコードはpreludeの型をパスを付けずに使うので、それらも含める
+const RESERVED_TYPE_NAMES: [&str; 13] = [
+  "ParseError",
+  "Ordering",
+  "CodeType",
+  "Visitor",
+  "Fold",
+  "ParseTracer",
+  "StderrTracer",
+  "Result",
+  "Box",
+  "Vec",
+  "Option",
+  "String",
+  "Sized",
+];
+
+/// 型を省略した規則について、型やメソッドの名前が重ならないことと、列挙型のvariantにラベルが付いていることを確認する
+pub fn check_auto_rules(bnfs: &[types::Bnf]) -> Result<(), error::Error> {
+  let auto_bnfs: Vec<types::Bnf> = bnfs
+    .iter()
+    .filter(|bnf| is_auto_rule(bnf))
+    .cloned()
+    .collect();
+  cst::check_type_names(&auto_bnfs, &RESERVED_TYPE_NAMES)?;
+  for bnf in auto_bnfs.iter() {
+    let (rng, name, _, code_lst) = get_bnf(bnf);
+    // `Visitor`と`Fold`のトークンのメソッドと名前が重なる
+    if name == "token" {
+      return Err(error::Error::GrammarError(
+        error::GrammarError::ReservedRuleName(*rng, name.clone()),
+      ));
+    }
+    if is_struct(code_lst) {
+      continue;
+    }
+    let mut label_set = HashSet::new();
+    for (i, (_, (label_rng, label))) in code_lst.iter().enumerate() {
+      if label.is_empty() {
+        return Err(error::Error::GrammarError(
+          error::GrammarError::MissingLabel(*label_rng, name.clone(), i),
+        ));
+      }
+      if !label_set.insert(label) {
+        return Err(error::Error::GrammarError(
+          error::GrammarError::DuplicateLabel(*label_rng, name.clone(), label.clone()),
+        ));
+      }
+    }
+  }
+  Ok(())
+}
+
+// 型を省略した規則ごとに、フィールドを辿って型が含みうる型を省略した規則の集合を作る
+fn make_reachable_map(bnfs: &[types::Bnf]) -> HashMap<&String, HashSet<&String>> {
+  let mut edge_map: HashMap<&String, Vec<&String>> = HashMap::new();
+  for bnf in bnfs.iter().filter(|bnf| is_auto_rule(bnf)) {
+    let (_, name, _, code_lst) = get_bnf(bnf);
+    let edge_lst = edge_map.entry(name).or_default();
+    for (fn_or_token_lst, _) in code_lst.iter() {
+      for (field_name, fn_or_token) in fn_or_token_lst.iter() {
+        if let types::FnOrToken::Function(_, rule_name) = fn_or_token {
+          if is_field_name(field_name) {
+            edge_lst.push(rule_name)
+          }
+        }
+      }
+    }
+  }
+  let mut reachable_map = HashMap::new();
+  for name in edge_map.keys() {
+    let mut reachable_set = HashSet::new();
+    let mut stack = edge_map[name].clone();
+    while let Some(next) = stack.pop() {
+      if reachable_set.insert(next) {
+        if let Some(edge_lst) = edge_map.get(next) {
+          stack.extend(edge_lst.iter())
+        }
+      }
+    }
+    reachable_map.insert(*name, reachable_set);
+  }
+  reachable_map
+}
+
+/// 型を省略した規則から生成する型を、規則の順に並べる
+///
+/// `check_auto_rules`で確認した後に使う。
+pub fn make_ast_type_lst(term: &types::Term) -> Vec<AstType> {
+  let (_, ((_, main_type_str), _), bnfs) = term;
+  let reachable_map = make_reachable_map(bnfs);
+  let index_map: HashMap<&String, usize> = bnfs
+    .iter()
+    .enumerate()
+    .map(|(i, bnf)| (get_bnf(bnf).1, i))
+    .collect();
+  let type_str_map: HashMap<&String, String> = bnfs
+    .iter()
+    .map(|bnf| {
+      let (_, name, (_, type_str), _) = get_bnf(bnf);
+      if is_auto_rule(bnf) {
+        (name, cst::to_type_name(name))
+      } else {
+        (name, type_str.clone())
+      }
+    })
+    .collect();
+  let make_field_lst = |rule_name: &String, fn_or_token_lst: &[(String, types::FnOrToken)]| {
+    fn_or_token_lst
+      .iter()
+      .filter(|(field_name, _)| is_field_name(field_name))
+      .map(|(field_name, fn_or_token)| match fn_or_token {
+        types::FnOrToken::Function(_, name) => {
+          let reachable_opt = reachable_map.get(name);
+          Field {
+            name: field_name.clone(),
+            // 無い規則はパーサを生成するときにエラーになる
+            type_str: type_str_map.get(name).cloned().unwrap_or_default(),
+            // 循環する参照のうち、文法の中で前（か同じ）の規則への参照だけを`Box`に入れる
+            // 循環の中には必ずそのような参照があるので、型の大きさが決まる
+            is_boxed: matches!(reachable_opt, Some(set) if set.contains(rule_name))
+              && index_map[name] <= index_map[rule_name],
+            kind: match reachable_opt {
+              Some(_) => FieldKind::AutoRule(name.clone()),
+              None => FieldKind::Rule(name.clone()),
+            },
+          }
+        }
+        types::FnOrToken::Token(_, _) => Field {
+          name: field_name.clone(),
+          type_str: main_type_str.clone(),
+          is_boxed: false,
+          kind: FieldKind::Token,
+        },
+      })
+      .collect()
+  };
+  bnfs
+    .iter()
+    .filter(|bnf| is_auto_rule(bnf))
+    .map(|bnf| {
+      let (_, name, _, code_lst) = get_bnf(bnf);
+      let type_name = cst::to_type_name(name);
+      if is_struct(code_lst) {
+        AstType::Struct(
+          type_name,
+          name.clone(),
+          make_field_lst(name, &code_lst[0].0),
+        )
+      } else {
+        let variant_lst = code_lst
+          .iter()
+          .map(|(fn_or_token_lst, (_, label))| {
+            (label.clone(), make_field_lst(name, fn_or_token_lst))
+          })
+          .collect();
+        AstType::Enum(type_name, name.clone(), variant_lst)
+      }
+    })
+    .collect()
+}
+
+impl Field {
+  fn to_type_str(&self) -> String {
+    if self.is_boxed {
+      format!("Box<{}>", self.type_str)
+    } else {
+      self.type_str.clone()
+    }
+  }
+  fn to_init_str(&self) -> String {
+    if self.is_boxed {
+      format!("{}: Box::new({})", self.name, self.name)
+    } else {
+      self.name.clone()
+    }
+  }
+}
+
+// `Name { a, b: Box::new(b) }`の形の値を作るコード
+fn make_construct_str(path: &str, field_lst: &[Field]) -> String {
+  if field_lst.is_empty() {
+    format!("{} {{}}", path)
+  } else {
+    let init_lst: Vec<String> = field_lst.iter().map(|field| field.to_init_str()).collect();
+    format!("{} {{ {} }}", path, init_lst.join(", "))
+  }
+}
+
+/// 型を省略した規則の型の文字列とアクションを、生成した型とその値を作るコードに置き換えた文法を作る
+pub fn make_ast_term(term: &types::Term, ast_type_lst: &[AstType]) -> types::Term {
+  let (head, setting, bnfs) = term;
+  let ast_type_map: HashMap<&String, &AstType> = ast_type_lst
+    .iter()
+    .map(|ast_type| (ast_type.rule_name(), ast_type))
+    .collect();
+  let ast_bnfs = bnfs
+    .iter()
+    .map(|bnf| {
+      let (rng, name, (type_rng, _), code_lst) = get_bnf(bnf);
+      let ast_type = match ast_type_map.get(name) {
+        Some(ast_type) => ast_type,
+        None => return bnf.clone(),
+      };
+      let ast_type_str = (*type_rng, ast_type.type_name().clone());
+      let ast_code_lst = code_lst
+        .iter()
+        .enumerate()
+        .map(|(i, (fn_or_token_lst, (code_rng, _)))| {
+          let code = match ast_type {
+            AstType::Struct(type_name, _, field_lst) => make_construct_str(type_name, field_lst),
+            AstType::Enum(type_name, _, variant_lst) => {
+              let (label, field_lst) = &variant_lst[i];
+              let path = format!("{}::{}", type_name, label);
+              if field_lst.is_empty() {
+                path
+              } else {
+                make_construct_str(&path, field_lst)
+              }
+            }
+          };
+          (fn_or_token_lst.clone(), (*code_rng, code))
+        })
+        .collect();
+      match bnf {
+        types::Bnf::Pub(_, _, _, _) => {
+          types::Bnf::Pub(*rng, name.clone(), ast_type_str, ast_code_lst)
+        }
+        types::Bnf::NonPub(_, _, _, _) => {
+          types::Bnf::NonPub(*rng, name.clone(), ast_type_str, ast_code_lst)
+        }
+      }
+    })
+    .collect();
+  (head.clone(), setting.clone(), ast_bnfs)
+}
+
+/// 生成する型の定義
+pub fn make_ast_type_str(ast_type_lst: &[AstType]) -> String {
+  let mut s = String::new();
+  for ast_type in ast_type_lst.iter() {
+    s.push_str(&format!(
+      "
+/// The syntax tree of the rule `{}`.
+#[derive(Debug, Clone, PartialEq)]
+",
+      ast_type.rule_name()
+    ));
+    match ast_type {
+      AstType::Struct(type_name, _, field_lst) => {
+        s.push_str(&format!("pub struct {} {{\n", type_name));
+        for field in field_lst.iter() {
+          s.push_str(&format!("  pub {}: {},\n", field.name, field.to_type_str()))
+        }
+      }
+      AstType::Enum(type_name, _, variant_lst) => {
+        s.push_str(&format!("pub enum {} {{\n", type_name));
+        for (label, field_lst) in variant_lst.iter() {
+          if field_lst.is_empty() {
+            s.push_str(&format!("  {},\n", label))
+          } else {
+            let field_str_lst: Vec<String> = field_lst
+              .iter()
+              .map(|field| format!("{}: {}", field.name, field.to_type_str()))
+              .collect();
+            s.push_str(&format!(
+              "  {} {{ {} }},\n",
+              label,
+              field_str_lst.join(", ")
+            ))
+          }
+        }
+      }
+    }
+    s.push_str("}\n")
+  }
+  s
+}
+
+#[test]
+fn check_ast_type() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_NUM => \"'0'\",
+    Tok_PLUS => \"'+'\",
+    Tok_EOF => \"'$'\",
+  }
+}
+pub main: \"Expr\" = {
+  <e: expr> <_eof: Tok_EOF> => {\"e\"},
+};
+expr = {
+  <lhs: Tok_NUM> <tail: expr_tail>
+};
+expr_tail = {
+  <_p: Tok_PLUS> <rhs: expr> => Add,
+  => Empty,
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let (_, _, bnfs) = &term;
+  assert!(!is_auto_rule(&bnfs[0]));
+  assert!(is_auto_rule(&bnfs[1]));
+  assert!(check_auto_rules(bnfs).is_ok());
+  let ast_type_lst = make_ast_type_lst(&term);
+  assert_eq!(
+    make_ast_type_str(&ast_type_lst),
+    "
+/// The syntax tree of the rule `expr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+  pub lhs: Token,
+  pub tail: ExprTail,
+}
+
+/// The syntax tree of the rule `expr_tail`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprTail {
+  Add { rhs: Box<Expr> },
+  Empty,
+}
+"
+  );
+  let (_, _, ast_bnfs) = make_ast_term(&term, &ast_type_lst);
+  match (&ast_bnfs[1], &ast_bnfs[2]) {
+    (
+      types::Bnf::NonPub(_, _, (_, expr_type_str), expr_code_lst),
+      types::Bnf::NonPub(_, _, (_, tail_type_str), tail_code_lst),
+    ) => {
+      assert_eq!(expr_type_str, "Expr");
+      assert_eq!((expr_code_lst[0].1).1, "Expr { lhs, tail }");
+      assert_eq!(tail_type_str, "ExprTail");
+      assert_eq!(
+        (tail_code_lst[0].1).1,
+        "ExprTail::Add { rhs: Box::new(rhs) }"
+      );
+      assert_eq!((tail_code_lst[1].1).1, "ExprTail::Empty");
+    }
+    bnfs => panic!("{:?}", bnfs),
+  }
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("=> Add", "")).unwrap();
+  assert!(matches!(
+    check_auto_rules(&bnfs),
+    Err(error::Error::GrammarError(
+      error::GrammarError::MissingLabel(_, _, _)
+    ))
+  ));
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("Empty", "Add")).unwrap();
+  assert!(matches!(
+    check_auto_rules(&bnfs),
+    Err(error::Error::GrammarError(
+      error::GrammarError::DuplicateLabel(_, _, _)
+    ))
+  ));
+  // `pub struct Result`が`std::result::Result`を隠してしまう
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("expr =", "result =")).unwrap();
+  assert!(matches!(
+    check_auto_rules(&bnfs),
+    Err(error::Error::GrammarError(
+      error::GrammarError::ReservedTypeName(_, _, _)
+    ))
+  ));
+  for rule_name in [
+    "box",
+    "vec",
+    "option",
+    "string",
+    "sized",
+    "visitor",
+    "parse_error",
+  ]
+  .iter()
+  {
+    let grammar = input.replace("expr_tail", rule_name);
+    let (_, _, bnfs) = super::super::frontend::get_ast(&grammar).unwrap();
+    let err = check_auto_rules(&bnfs).unwrap_err();
+    assert!(
+      error::make_error_msg(err, Some("test.mkr"), Some(&grammar)).starts_with(&format!(
+        "![grammar error]\n  rule \"{}\" would generate",
+        rule_name
+      ))
+    );
+  }
+}
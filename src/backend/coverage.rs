@@ -0,0 +1,214 @@
+use super::error;
+use super::types;
+
+// 生成されたパーサで、各規則の各選択肢が選ばれた回数を数える
+// カウンタは全ての規則の全ての選択肢（空の選択肢も含む）に、規則の順・選択肢の順で番号を付ける
+// 数えた結果は`規則の名前 選択肢の番号 回数`を1行ずつ並べたテキストとして書き出し、
+// `llmaker coverage`で文法ファイル上の位置と対応させる
+
+fn get_code_lst(bnf: &types::Bnf) -> (&String, &Vec<types::Code>) {
+  match bnf {
+    types::Bnf::Pub(_, name, _, code_lst) | types::Bnf::NonPub(_, name, _, code_lst) => {
+      (name, code_lst)
+    }
+  }
+}
+
+/// 各規則の最初の選択肢のカウンタの番号を、規則の順に返す
+pub fn make_counter_base_lst(bnfs: &[types::Bnf]) -> Vec<usize> {
+  let mut base_lst = Vec::new();
+  let mut base = 0;
+  for bnf in bnfs.iter() {
+    base_lst.push(base);
+    base += get_code_lst(bnf).1.len();
+  }
+  base_lst
+}
+
+/// 選択肢が選ばれたときにカウンタを増やすコード
+pub fn make_counter_code(index: usize) -> String {
+  format!(
+    "_COVERAGE_COUNTERS[{}].fetch_add(1, std::sync::atomic::Ordering::Relaxed);",
+    index
+  )
+}
+
+/// カウンタと、数えた結果を取り出す関数のコード
+pub fn make_coverage_fn_str(bnfs: &[types::Bnf], input_file_name: &str) -> String {
+  let mut counter_str = String::new();
+  let mut alternative_str = String::new();
+  let mut count = 0;
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = get_code_lst(bnf);
+    for i in 0..code_lst.len() {
+      counter_str.push_str("  std::sync::atomic::AtomicUsize::new(0),\n");
+      alternative_str.push_str(&format!("  ({:?}, {}),\n", name, i));
+      count += 1;
+    }
+  }
+  format!(
+    "
+static _COVERAGE_COUNTERS: [std::sync::atomic::AtomicUsize; {count}] = [
+{counter_str}];
+
+const _COVERAGE_ALTERNATIVES: [(&str, usize); {count}] = [
+{alternative_str}];
+
+/// Returns how many times each alternative of each rule has been chosen,
+/// as `(rule name, alternative index, count)`.
+#[allow(dead_code)]
+pub fn coverage_counts() -> Vec<(&'static str, usize, usize)> {{
+  _COVERAGE_ALTERNATIVES
+    .iter()
+    .zip(_COVERAGE_COUNTERS.iter())
+    .map(|((name, i), counter)| (*name, *i, counter.load(std::sync::atomic::Ordering::Relaxed)))
+    .collect()
+}}
+
+/// Returns the counts in the format read by `llmaker coverage`.
+#[allow(dead_code)]
+pub fn coverage_dump() -> String {{
+  let mut s = String::from({header:?});
+  for (name, i, count) in coverage_counts() {{
+    s.push_str(&format!(\"{{}} {{}} {{}}\\n\", name, i, count));
+  }}
+  s
+}}
+
+/// Sets all the counts to zero.
+#[allow(dead_code)]
+pub fn reset_coverage() {{
+  for counter in _COVERAGE_COUNTERS.iter() {{
+    counter.store(0, std::sync::atomic::Ordering::Relaxed);
+  }}
+}}
+",
+    count = count,
+    counter_str = counter_str,
+    alternative_str = alternative_str,
+    header = format!("# llmaker coverage {}\n", input_file_name)
+  )
+}
+
+/// `coverage_dump`で書き出したテキストを読み、`(規則の名前, 選択肢の番号, 回数)`の列を返す
+///
+/// 空の行と`#`で始まる行は読み飛ばす。
+pub fn parse_coverage_dump(input: &str) -> Result<Vec<(String, usize, usize)>, error::Error> {
+  let mut count_lst = Vec::new();
+  for (i, line) in input.lines().enumerate() {
+    let s = line.trim();
+    if s.is_empty() || s.starts_with('#') {
+      continue;
+    }
+    let words: Vec<&str> = s.split_whitespace().collect();
+    match words.as_slice() {
+      [name, index_str, count_str] => match (index_str.parse(), count_str.parse()) {
+        (Ok(index), Ok(count)) => count_lst.push((name.to_string(), index, count)),
+        _ => {
+          return Err(error::Error::CoverageError(
+            error::CoverageError::InvalidCoverageLine(i + 1, s.to_string()),
+          ))
+        }
+      },
+      _ => {
+        return Err(error::Error::CoverageError(
+          error::CoverageError::InvalidCoverageLine(i + 1, s.to_string()),
+        ))
+      }
+    }
+  }
+  Ok(count_lst)
+}
+
+/// 規則の選択肢ごとに、文法ファイル上の位置と選ばれた回数を並べた報告を作る
+///
+/// 同じ選択肢の回数が何度も現れたときは足し合わせる。
+/// 文法に無い規則や選択肢の回数があるときは、文法ファイルが変わったものとしてエラーを返す。
+/// `is_uncovered_only`がtrueなら、一度も選ばれなかった選択肢だけを並べる。
+pub fn make_coverage_report_str(
+  term: &types::Term,
+  input_file_name: &str,
+  input: &str,
+  count_lst: &[(String, usize, usize)],
+  is_uncovered_only: bool,
+) -> Result<String, error::Error> {
+  let (_, _, bnfs) = term;
+  let mut total_lst: Vec<Vec<usize>> = bnfs
+    .iter()
+    .map(|bnf| vec![0; get_code_lst(bnf).1.len()])
+    .collect();
+  for (name, index, count) in count_lst.iter() {
+    let total_opt = bnfs
+      .iter()
+      .position(|bnf| get_code_lst(bnf).0 == name)
+      .and_then(|i| total_lst[i].get_mut(*index));
+    match total_opt {
+      Some(total) => *total += count,
+      None => {
+        return Err(error::Error::CoverageError(
+          error::CoverageError::NotFoundAlternative(name.clone(), *index),
+        ))
+      }
+    }
+  }
+  let mut s = String::new();
+  let mut covered_count = 0;
+  let mut alternative_count = 0;
+  for (bnf, total_lst) in bnfs.iter().zip(total_lst.iter()) {
+    let (name, code_lst) = get_code_lst(bnf);
+    for (i, ((_, (code_rng, _)), total)) in code_lst.iter().zip(total_lst.iter()).enumerate() {
+      alternative_count += 1;
+      if *total > 0 {
+        covered_count += 1;
+        if is_uncovered_only {
+          continue;
+        }
+      }
+      let (_, (line, column), _) = error::get_error_point(*code_rng, input.as_bytes());
+      let count_str = if *total == 0 {
+        "never chosen".to_string()
+      } else {
+        format!("chosen {} times", total)
+      };
+      s.push_str(&format!(
+        "{}:{}:{}: {} #{}: {}\n",
+        input_file_name, line, column, name, i, count_str
+      ))
+    }
+  }
+  s.push_str(&format!(
+    "{} of {} alternatives covered\n",
+    covered_count, alternative_count
+  ));
+  Ok(s)
+}
+
+#[test]
+fn check_coverage_report() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_a: Tok_A> <_m: main> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let (_, _, bnfs) = &term;
+  assert_eq!(make_counter_base_lst(bnfs), vec![0]);
+  let count_lst = parse_coverage_dump("# llmaker coverage a.mkr\nmain 0 0\nmain 1 2\n").unwrap();
+  let report = make_coverage_report_str(&term, "a.mkr", input, &count_lst, false).unwrap();
+  assert_eq!(
+    report,
+    "a.mkr:8:30: main #0: never chosen
+a.mkr:9:7: main #1: chosen 2 times
+1 of 2 alternatives covered
+"
+  );
+  let count_lst = parse_coverage_dump("main 2 1").unwrap();
+  assert!(make_coverage_report_str(&term, "a.mkr", input, &count_lst, false).is_err());
+  assert!(parse_coverage_dump("main 0").is_err());
+}
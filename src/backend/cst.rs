@@ -0,0 +1,238 @@
+use super::error;
+use super::types;
+use std::collections::HashMap;
+
+// 具象構文木を作るパーサを生成する
+// 文法のアクションと型は使わず、全ての規則の型を`Node`に、
+// 全ての選択肢のアクションを「選んだ選択肢の番号と、読んだトークン・子のNodeを順に並べたもの」を作るコードに置き換えた
+// 文法を作り、それを通常と同じように生成する
+
+/// 規則の名前から、生成する型やvariantの名前を作る
+///
+/// `_`で区切られた部分の先頭を大文字にしてつなげる（`expr_tail`なら`ExprTail`）。
+pub fn to_type_name(rule_name: &str) -> String {
+  let mut type_name = String::new();
+  for part in rule_name.split('_') {
+    let mut chars = part.chars();
+    if let Some(c) = chars.next() {
+      type_name.extend(c.to_uppercase());
+      type_name.push_str(chars.as_str())
+    }
+  }
+  type_name
+}
+
+/// `Node`と一緒に生成する型の名前
+pub const RESERVED_TYPE_NAMES: [&str; 2] = ["Node", "Child"];
+
+/// 異なる規則から同じ型の名前が作られないことと、生成されるコードで使われている`reserved_type_names`の名前が作られないことを確認する
+pub fn check_type_names(
+  bnfs: &[types::Bnf],
+  reserved_type_names: &[&str],
+) -> Result<(), error::Error> {
+  let mut rule_name_map: HashMap<String, &String> = HashMap::new();
+  for bnf in bnfs.iter() {
+    let (rng, name) = match bnf {
+      types::Bnf::Pub(rng, name, _, _) | types::Bnf::NonPub(rng, name, _, _) => (rng, name),
+    };
+    let type_name = to_type_name(name);
+    if reserved_type_names.contains(&type_name.as_str()) {
+      return Err(error::Error::GrammarError(
+        error::GrammarError::ReservedTypeName(*rng, name.clone(), type_name),
+      ));
+    }
+    if let Some(other_name) = rule_name_map.get(&type_name) {
+      return Err(error::Error::ConfigError(
+        error::ConfigError::DuplicateTypeName(
+          *rng,
+          name.clone(),
+          other_name.to_string(),
+          type_name,
+        ),
+      ));
+    }
+    rule_name_map.insert(type_name, name);
+  }
+  Ok(())
+}
+
+// 選択肢の中のi番目の記号を束縛する変数の名前
+fn child_var_name(i: usize) -> String {
+  format!("_child{}", i)
+}
+
+/// 全ての規則が`Node`を返すように書き換えた文法を作る
+///
+/// 位置情報は元の文法のものを残すので、生成されたコードの位置のコメントは元の文法ファイルを指す。
+pub fn make_cst_term(term: &types::Term) -> types::Term {
+  let (head, setting, bnfs) = term;
+  let cst_bnfs = bnfs
+    .iter()
+    .map(|bnf| {
+      let (name, (type_rng, _), code_lst) = match bnf {
+        types::Bnf::Pub(_, name, type_str, code_lst)
+        | types::Bnf::NonPub(_, name, type_str, code_lst) => (name, type_str, code_lst),
+      };
+      let cst_type_str = (*type_rng, "Node".to_string());
+      let cst_code_lst = code_lst
+        .iter()
+        .enumerate()
+        .map(|(i, (fn_or_token_lst, (code_rng, _)))| {
+          let mut child_str_lst = Vec::new();
+          let mut cst_fn_or_token_lst = Vec::new();
+          for (j, (_, fn_or_token)) in fn_or_token_lst.iter().enumerate() {
+            let var_name = child_var_name(j);
+            child_str_lst.push(match fn_or_token {
+              types::FnOrToken::Function(_, _) => format!("Child::Node({})", var_name),
+              types::FnOrToken::Token(_, _) => format!("Child::Token({})", var_name),
+            });
+            cst_fn_or_token_lst.push((var_name, fn_or_token.clone()))
+          }
+          let code = format!(
+            "Node::{}({}, vec![{}])",
+            to_type_name(name),
+            i,
+            child_str_lst.join(", ")
+          );
+          (cst_fn_or_token_lst, (*code_rng, code))
+        })
+        .collect();
+      match bnf {
+        types::Bnf::Pub(rng, _, _, _) => {
+          types::Bnf::Pub(*rng, name.clone(), cst_type_str, cst_code_lst)
+        }
+        types::Bnf::NonPub(rng, _, _, _) => {
+          types::Bnf::NonPub(*rng, name.clone(), cst_type_str, cst_code_lst)
+        }
+      }
+    })
+    .collect();
+  (head.clone(), setting.clone(), cst_bnfs)
+}
+
+/// `Node`と`Child`の型の定義
+pub fn make_node_type_str(bnfs: &[types::Bnf], main_type_str: &str) -> String {
+  let mut variant_str = String::new();
+  let mut rule_name_str = String::new();
+  let mut alternative_str = String::new();
+  let mut children_str = String::new();
+  for bnf in bnfs.iter() {
+    let name = match bnf {
+      types::Bnf::Pub(_, name, _, _) | types::Bnf::NonPub(_, name, _, _) => name,
+    };
+    let type_name = to_type_name(name);
+    variant_str.push_str(&format!("  {}(usize, Vec<Child>),\n", type_name));
+    rule_name_str.push_str(&format!("      Node::{}(_, _) => {:?},\n", type_name, name));
+    alternative_str.push_str(&format!(
+      "      Node::{}(alternative, _) => *alternative,\n",
+      type_name
+    ));
+    children_str.push_str(&format!(
+      "      Node::{}(_, children) => children,\n",
+      type_name
+    ));
+  }
+  format!(
+    "
+/// A node of the concrete syntax tree, with one variant per rule.
+///
+/// Each variant holds the index of the chosen alternative (counted from 0)
+/// and the tokens and nodes read by it in source order.
+#[derive(Debug, Clone)]
+pub enum Node {{
+{variants}}}
+
+/// A child of a node: a token or the node of a rule.
+#[derive(Debug, Clone)]
+pub enum Child {{
+  Token({main_type}),
+  Node(Node),
+}}
+
+#[allow(dead_code)]
+impl Node {{
+  /// Returns the name of the rule in the grammar.
+  pub fn rule_name(&self) -> &'static str {{
+    match self {{
+{rule_names}    }}
+  }}
+
+  /// Returns the index of the chosen alternative, counted from 0.
+  pub fn alternative(&self) -> usize {{
+    match self {{
+{alternatives}    }}
+  }}
+
+  /// Returns the tokens and nodes read by the chosen alternative in source order.
+  pub fn children(&self) -> &[Child] {{
+    match self {{
+{children}    }}
+  }}
+
+  /// Returns all the tokens under this node in source order.
+  pub fn tokens(&self) -> Vec<&{main_type}> {{
+    let mut tokens = Vec::new();
+    for child in self.children().iter() {{
+      match child {{
+        Child::Token(tok) => tokens.push(tok),
+        Child::Node(node) => tokens.append(&mut node.tokens()),
+      }}
+    }}
+    tokens
+  }}
+}}
+",
+    variants = variant_str,
+    main_type = main_type_str,
+    rule_names = rule_name_str,
+    alternatives = alternative_str,
+    children = children_str
+  )
+}
+
+#[test]
+fn check_cst() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"usize\" = {
+  <a: Tok_A> <rest: main_tail> => {\"rest + 1\"},
+};
+main_tail: \"usize\" = {
+  <m: main> => {\"m\"},
+  => {\"0\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  assert_eq!(to_type_name("main_tail"), "MainTail");
+  assert_eq!(to_type_name("_a__b"), "AB");
+  let (_, _, bnfs) = make_cst_term(&term);
+  match &bnfs[1] {
+    types::Bnf::NonPub(_, name, (_, type_str), code_lst) => {
+      assert_eq!(name, "main_tail");
+      assert_eq!(type_str, "Node");
+      assert_eq!(code_lst[0].0[0].0, "_child0");
+      assert_eq!(
+        (code_lst[0].1).1,
+        "Node::MainTail(0, vec![Child::Node(_child0)])"
+      );
+      assert_eq!((code_lst[1].1).1, "Node::MainTail(1, vec![])");
+    }
+    bnf => panic!("{:?}", bnf),
+  }
+  let (_, _, bnfs) =
+    super::super::frontend::get_ast(&input.replace("main_tail", "mainTail")).unwrap();
+  assert!(check_type_names(&bnfs, &RESERVED_TYPE_NAMES).is_ok());
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("main_tail", "_main")).unwrap();
+  assert!(check_type_names(&bnfs, &RESERVED_TYPE_NAMES).is_err());
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("main_tail", "child")).unwrap();
+  assert!(matches!(
+    check_type_names(&bnfs, &RESERVED_TYPE_NAMES),
+    Err(error::Error::GrammarError(
+      error::GrammarError::ReservedTypeName(_, _, _)
+    ))
+  ));
+}
@@ -0,0 +1,73 @@
+use super::types;
+
+// 規則の間の依存関係をGraphvizのdot形式で出力する
+// pubな規則は二重の枠で表す
+
+fn escape_dot_str(s: &str) -> String {
+  s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn make_dot_str(term: &types::Term) -> String {
+  let (_, _, bnfs) = term;
+  let mut s = String::new();
+  s.push_str("digraph grammar {\n");
+  s.push_str("  rankdir=LR;\n");
+  s.push_str("  node [shape=box];\n");
+  for bnf in bnfs.iter() {
+    match bnf {
+      types::Bnf::Pub(_, name, _, _) => s.push_str(&format!(
+        "  \"{}\" [peripheries=2];\n",
+        escape_dot_str(name)
+      )),
+      types::Bnf::NonPub(_, name, _, _) => {
+        s.push_str(&format!("  \"{}\";\n", escape_dot_str(name)))
+      }
+    }
+  }
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    let mut dependencies: Vec<&String> = Vec::new();
+    for (fn_or_token_lst, _) in code_lst.iter() {
+      for (_, fn_or_token) in fn_or_token_lst.iter() {
+        if let types::FnOrToken::Function(_, fn_name) = fn_or_token {
+          if !dependencies.contains(&fn_name) {
+            dependencies.push(fn_name)
+          }
+        }
+      }
+    }
+    for fn_name in dependencies.iter() {
+      s.push_str(&format!(
+        "  \"{}\" -> \"{}\";\n",
+        escape_dot_str(name),
+        escape_dot_str(fn_name)
+      ))
+    }
+  }
+  s.push_str("}\n");
+  s
+}
+
+#[test]
+fn check_make_dot_str() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: sub> <_y: sub> => {\"()\"},
+};
+sub: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let dot_str = make_dot_str(&term);
+  assert!(dot_str.contains("  \"main\" [peripheries=2];\n  \"sub\";\n"));
+  assert_eq!(dot_str.matches("\"main\" -> \"sub\"").count(), 1);
+}
@@ -0,0 +1,212 @@
+use super::analysis;
+use super::error;
+use super::interpreter;
+use super::types;
+
+// 文法のテスト
+// 1つのテストは`test <rule> accepts "<tokens>"`または`test <rule> rejects "<tokens>"`の1行で、
+// <tokens>は空白で区切ったトークン名の列
+// 文法ファイルの中では`//!`で始まるコメントに書き、テスト用のファイルには1行に1つずつ書く
+
+// 文法ファイルの中でテストを書くコメントの始まり
+const INLINE_TEST_PREFIX: &str = "//!";
+
+/// 文法のテスト1つ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+  /// テストが書かれている行（1から数える）
+  pub line: usize,
+  pub rule_name: String,
+  /// acceptsならtrue、rejectsならfalse
+  pub is_accept: bool,
+  pub tokens: Vec<String>,
+}
+
+impl TestCase {
+  /// `expr accepts "Tok_A Tok_B"`の形の、テストの内容
+  pub fn to_str(&self) -> String {
+    format!(
+      "{} {} \"{}\"",
+      self.rule_name,
+      if self.is_accept { "accepts" } else { "rejects" },
+      self.tokens.join(" ")
+    )
+  }
+}
+
+/// テストの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestResult {
+  Passed,
+  /// rejectsのテストで、最後まで読めてしまった
+  UnexpectedlyAccepted,
+  /// acceptsのテストで、読めなかった
+  UnexpectedlyRejected(interpreter::ParseFailure),
+}
+
+fn parse_test_line(line: usize, s: &str) -> Result<TestCase, error::Error> {
+  let invalid = || {
+    error::Error::TestError(error::TestError::InvalidTestCase(
+      line,
+      s.trim().to_string(),
+    ))
+  };
+  let rest = s.trim().strip_prefix("test ").ok_or_else(invalid)?;
+  let mut words = rest.trim_start().splitn(2, ' ');
+  let rule_name = words.next().ok_or_else(invalid)?;
+  let rest = words.next().ok_or_else(invalid)?.trim_start();
+  let (is_accept, rest) = if let Some(rest) = rest.strip_prefix("accepts ") {
+    (true, rest)
+  } else if let Some(rest) = rest.strip_prefix("rejects ") {
+    (false, rest)
+  } else {
+    return Err(invalid());
+  };
+  let tokens_str = rest
+    .trim()
+    .strip_prefix('"')
+    .and_then(|s| s.strip_suffix('"'))
+    .ok_or_else(invalid)?;
+  Ok(TestCase {
+    line,
+    rule_name: rule_name.to_string(),
+    is_accept,
+    tokens: tokens_str
+      .split_whitespace()
+      .map(|s| s.to_string())
+      .collect(),
+  })
+}
+
+/// 文法ファイルの中の、`//!`で始まるコメントに書かれたテストを読む
+pub fn parse_inline_test_cases(input: &str) -> Result<Vec<TestCase>, error::Error> {
+  let mut test_case_lst = Vec::new();
+  for (i, line) in input.lines().enumerate() {
+    if let Some(s) = line.trim_start().strip_prefix(INLINE_TEST_PREFIX) {
+      test_case_lst.push(parse_test_line(i + 1, s)?)
+    }
+  }
+  Ok(test_case_lst)
+}
+
+/// テスト用のファイルに書かれたテストを読む
+///
+/// 空の行と`//`で始まる行は読み飛ばす。
+pub fn parse_test_file(input: &str) -> Result<Vec<TestCase>, error::Error> {
+  let mut test_case_lst = Vec::new();
+  for (i, line) in input.lines().enumerate() {
+    let s = line.trim();
+    if !s.is_empty() && !s.starts_with("//") {
+      test_case_lst.push(parse_test_line(i + 1, s)?)
+    }
+  }
+  Ok(test_case_lst)
+}
+
+/// テストを実行する
+///
+/// 文法に無い規則やトークンを使っているときはエラーを返す。
+pub fn run_test_case(term: &types::Term, test_case: &TestCase) -> Result<TestResult, error::Error> {
+  if !interpreter::is_defined_rule(term, &test_case.rule_name) {
+    return Err(error::Error::TestError(error::TestError::NotFoundTestRule(
+      test_case.line,
+      test_case.rule_name.clone(),
+    )));
+  }
+  if let Some(tok_name) = interpreter::find_undefined_token(term, &test_case.tokens) {
+    return Err(error::Error::TestError(
+      error::TestError::NotFoundTestToken(test_case.line, tok_name.clone()),
+    ));
+  }
+  let result = interpreter::parse(term, &test_case.rule_name, &test_case.tokens);
+  Ok(match (result, test_case.is_accept) {
+    (Ok(_), true) | (Err(_), false) => TestResult::Passed,
+    (Ok(_), false) => TestResult::UnexpectedlyAccepted,
+    (Err(failure), true) => TestResult::UnexpectedlyRejected(failure),
+  })
+}
+
+/// 読み込みに失敗した位置と、来ることができたトークンの説明
+pub fn failure_to_str(failure: &interpreter::ParseFailure, tokens: &[String]) -> String {
+  let expected_str_lst: Vec<&str> = failure
+    .expected
+    .iter()
+    .map(|tok_name| {
+      if tok_name == analysis::END_OF_INPUT {
+        "end of input"
+      } else {
+        tok_name
+      }
+    })
+    .collect();
+  let found_str = match tokens.get(failure.pos) {
+    Some(tok_name) => format!("unexpected `{}` (token {})", tok_name, failure.pos + 1),
+    None => "unexpected end of input".to_string(),
+  };
+  format!("{}, expected {}", found_str, expected_str_lst.join(" or "))
+}
+
+#[test]
+fn check_run_test_case() {
+  let input = "//! test main accepts \"Tok_A Tok_B\"
+//! test main accepts \"Tok_B\"
+//! test main rejects \"Tok_A\"
+//! test main accepts \"Tok_B Tok_B\"
+grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+    Tok_B => \"'b'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: opt> <_b: Tok_B> => {\"()\"},
+};
+opt: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let test_case_lst = parse_inline_test_cases(input).unwrap();
+  assert_eq!(test_case_lst[0].to_str(), "main accepts \"Tok_A Tok_B\"");
+  let result_lst: Vec<TestResult> = test_case_lst
+    .iter()
+    .map(|test_case| run_test_case(&term, test_case).unwrap())
+    .collect();
+  assert_eq!(result_lst[0], TestResult::Passed);
+  assert_eq!(result_lst[1], TestResult::Passed);
+  assert_eq!(result_lst[2], TestResult::Passed);
+  match &result_lst[3] {
+    TestResult::UnexpectedlyRejected(failure) => assert_eq!(
+      failure_to_str(failure, &test_case_lst[3].tokens),
+      "unexpected `Tok_B` (token 2), expected end of input"
+    ),
+    result => panic!("{:?}", result),
+  }
+  assert!(parse_test_file("test main accepts Tok_A").is_err());
+
+  // 入力の終わりで空になりうる規則も、生成されるパーサと同じように空の選択肢を選ぶ
+  let input = "//! test expr accepts \"Tok_NUM\"
+//! test expr accepts \"Tok_NUM Tok_PLUS Tok_NUM\"
+//! test expr rejects \"Tok_NUM Tok_PLUS\"
+grammar;
+extern {
+  enum \"Token\" {
+    Tok_NUM => \"Token::Num\",
+    Tok_PLUS => \"Token::Plus\",
+  }
+}
+pub expr = {
+  <head: Tok_NUM> <tail: expr_tail> => Expr,
+};
+expr_tail = {
+  <plus: Tok_PLUS> <rest: expr> => More,
+  => End,
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  for test_case in parse_inline_test_cases(input).unwrap().iter() {
+    assert_eq!(run_test_case(&term, test_case).unwrap(), TestResult::Passed);
+  }
+}
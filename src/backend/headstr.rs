@@ -5,12 +5,12 @@ const HEADERMSG: &str = r#"
 // This file was generated by llmaker.
 //
 
-"use std::cmp::Ordering;"
+use std::cmp::Ordering;
 "#;
 
 pub fn head_to_str(headvec: types::Head, setting: types::Setting) -> String {
   let headstr = vecstr_to_str(headvec);
-  let (main_type_str, _) = setting;
+  let ((_, main_type_str), _) = setting;
   let parse_error_type_str = make_parse_erro_type_str(main_type_str);
   format!("{}\n{}\n\n{}", HEADERMSG, headstr, parse_error_type_str)
 }
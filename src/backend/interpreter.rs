@@ -0,0 +1,238 @@
+use super::analysis;
+use super::types;
+use std::collections::{BTreeSet, HashMap};
+
+// Rustのコードを生成せずに、文法をそのまま使ってトークン名の列を読み、構文木を作る
+// 選択肢は生成されるパーサと同じようにpredictで選び、どれにも当てはまらなければ空の選択肢を使う
+// 入力の終わりはEND_OF_INPUTとして扱う
+
+/// 読み込んだ結果の構文木
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseTree {
+  /// 規則の名前と選んだ選択肢の番号（0から数える）、選択肢の中の記号それぞれの構文木
+  Rule(String, usize, Vec<ParseTree>),
+  /// トークンの名前と、入力の中での番号（0から数える）
+  Token(String, usize),
+}
+
+impl ParseTree {
+  /// 1行に1つの規則またはトークンを、深さに合わせて字下げして並べる
+  ///
+  /// 規則は`expr #0`のように選んだ選択肢の番号を、トークンは`Tok_A (token 1)`のように入力の中での位置を付ける。
+  pub fn to_str(&self) -> String {
+    let mut s = String::new();
+    self.write_str(&mut s, 0);
+    s
+  }
+
+  fn write_str(&self, s: &mut String, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match self {
+      ParseTree::Rule(name, i, children) => {
+        s.push_str(&format!("{}{} #{}\n", indent, name, i));
+        for child in children.iter() {
+          child.write_str(s, depth + 1)
+        }
+      }
+      ParseTree::Token(name, pos) => {
+        s.push_str(&format!("{}{} (token {})\n", indent, name, pos + 1))
+      }
+    }
+  }
+}
+
+/// 読み込みに失敗した位置と、その位置に来ることができたトークン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure {
+  /// 失敗したトークンの番号（0から数える）。入力の終わりなら入力の長さと同じ
+  pub pos: usize,
+  /// その位置に来ることができたトークン（入力の終わりは`analysis::END_OF_INPUT`）
+  pub expected: BTreeSet<String>,
+}
+
+struct Interpreter<'a> {
+  code_lst_map: HashMap<&'a String, &'a Vec<types::Code>>,
+  predict_set_map: HashMap<String, Vec<BTreeSet<String>>>,
+  tokens: &'a [String],
+  pos: usize,
+  // 空の選択肢を選んだ位置と、そこで他の選択肢を選ぶことができたトークン
+  // その位置で失敗したときに、来ることができたトークンに加える
+  skipped_pos: usize,
+  skipped_set: BTreeSet<String>,
+}
+
+impl<'a> Interpreter<'a> {
+  fn next_token(&self) -> &str {
+    match self.tokens.get(self.pos) {
+      Some(tok_name) => tok_name,
+      None => analysis::END_OF_INPUT,
+    }
+  }
+
+  fn fail(&self, mut expected: BTreeSet<String>) -> ParseFailure {
+    if self.skipped_pos == self.pos {
+      expected.extend(self.skipped_set.iter().cloned())
+    }
+    ParseFailure {
+      pos: self.pos,
+      expected,
+    }
+  }
+
+  fn parse_rule(&mut self, name: &str) -> Result<ParseTree, ParseFailure> {
+    let code_lst = self.code_lst_map[&name.to_string()];
+    let predict_set_lst = &self.predict_set_map[name];
+    let next_token = self.next_token();
+    // 空の選択肢はpredictに当てはまるものが無いときに使う
+    let index_opt = code_lst
+      .iter()
+      .zip(predict_set_lst.iter())
+      .position(|((fn_or_token_lst, _), predict_set)| {
+        !fn_or_token_lst.is_empty() && predict_set.contains(next_token)
+      })
+      .or_else(|| {
+        code_lst
+          .iter()
+          .position(|(fn_or_token_lst, _)| fn_or_token_lst.is_empty())
+      });
+    let (i, (fn_or_token_lst, _)) = match index_opt {
+      Some(i) => {
+        if code_lst[i].0.is_empty() {
+          if self.skipped_pos != self.pos {
+            self.skipped_pos = self.pos;
+            self.skipped_set.clear();
+          }
+          self
+            .skipped_set
+            .extend(predict_set_lst.iter().flatten().cloned());
+        }
+        (i, &code_lst[i])
+      }
+      None => {
+        let expected = predict_set_lst.iter().flatten().cloned().collect();
+        return Err(self.fail(expected));
+      }
+    };
+    let mut children = Vec::new();
+    for (_, fn_or_token) in fn_or_token_lst.iter() {
+      match fn_or_token {
+        types::FnOrToken::Token(_, tok_name) => {
+          if self.next_token() == tok_name {
+            children.push(ParseTree::Token(tok_name.clone(), self.pos));
+            self.pos += 1
+          } else {
+            let mut expected = BTreeSet::new();
+            expected.insert(tok_name.clone());
+            return Err(self.fail(expected));
+          }
+        }
+        types::FnOrToken::Function(_, fn_name) => children.push(self.parse_rule(fn_name)?),
+      }
+    }
+    Ok(ParseTree::Rule(name.to_string(), i, children))
+  }
+}
+
+/// 文法に`name`という名前の規則があるか
+pub fn is_defined_rule(term: &types::Term, name: &str) -> bool {
+  let (_, _, bnfs) = term;
+  bnfs.iter().any(|bnf| match bnf {
+    types::Bnf::Pub(_, rule_name, _, _) | types::Bnf::NonPub(_, rule_name, _, _) => {
+      rule_name == name
+    }
+  })
+}
+
+/// `tokens`の中で、`extern`で宣言されていない最初のトークン名
+pub fn find_undefined_token<'a>(term: &types::Term, tokens: &'a [String]) -> Option<&'a String> {
+  let (_, (_, token_lst), _) = term;
+  tokens
+    .iter()
+    .find(|tok_name| !token_lst.iter().any(|(_, name, _)| &name == tok_name))
+}
+
+/// `rule_name`の規則として、トークン名の列`tokens`を最後まで読み、構文木を返す
+///
+/// 文法は`backend::to_string`の検査を通っている（未定義の規則や左再帰が無い）ものとし、
+/// `rule_name`と`tokens`の名前は`is_defined_rule`と`find_undefined_token`で確かめておく。
+pub fn parse(
+  term: &types::Term,
+  rule_name: &str,
+  tokens: &[String],
+) -> Result<ParseTree, ParseFailure> {
+  let (_, _, bnfs) = term;
+  let mut code_lst_map = HashMap::new();
+  for bnf in bnfs.iter() {
+    match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) | types::Bnf::NonPub(_, name, _, code_lst) => {
+        code_lst_map.insert(name, code_lst)
+      }
+    };
+  }
+  let mut interpreter = Interpreter {
+    code_lst_map,
+    predict_set_map: analysis::make_predict_set_map(bnfs),
+    tokens,
+    pos: 0,
+    skipped_pos: 0,
+    skipped_set: BTreeSet::new(),
+  };
+  let tree = interpreter.parse_rule(rule_name)?;
+  if interpreter.pos < tokens.len() {
+    let mut expected = BTreeSet::new();
+    expected.insert(analysis::END_OF_INPUT.to_string());
+    return Err(interpreter.fail(expected));
+  }
+  Ok(tree)
+}
+
+#[test]
+fn check_parse() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_NUM => \"Token::Num\",
+    Tok_PLUS => \"Token::Plus\",
+  }
+}
+pub expr: \"()\" = {
+  <_n: Tok_NUM> <_t: expr_tail> => {\"()\"},
+};
+expr_tail: \"()\" = {
+  <_p: Tok_PLUS> <_e: expr> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let tokens: Vec<String> = vec![
+    "Tok_NUM".to_string(),
+    "Tok_PLUS".to_string(),
+    "Tok_NUM".to_string(),
+  ];
+  let tree = parse(&term, "expr", &tokens).unwrap();
+  assert_eq!(
+    tree.to_str(),
+    "expr #0
+  Tok_NUM (token 1)
+  expr_tail #0
+    Tok_PLUS (token 2)
+    expr #0
+      Tok_NUM (token 3)
+      expr_tail #1
+"
+  );
+  let failure = parse(&term, "expr", &tokens[..2]).unwrap_err();
+  assert_eq!(failure.pos, 2);
+  assert_eq!(failure.expected.iter().collect::<Vec<_>>(), vec!["Tok_NUM"]);
+  let failure = parse(&term, "expr", &[tokens[0].clone(), tokens[0].clone()]).unwrap_err();
+  assert_eq!(failure.pos, 1);
+  assert_eq!(
+    failure.expected.iter().collect::<Vec<_>>(),
+    vec!["$", "Tok_PLUS"]
+  );
+  assert!(is_defined_rule(&term, "expr_tail"));
+  assert_eq!(
+    find_undefined_token(&term, &["Tok_MINUS".to_string()]).unwrap(),
+    "Tok_MINUS"
+  );
+}
@@ -1,42 +1,146 @@
+use super::analysis;
+use super::coverage;
 use super::error;
+use super::source_map;
+use super::trace;
 use super::types;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 pub fn make_parse_fn_fn_str(
   setting: types::Setting,
   bnfs: &[types::Bnf],
+  input_file_name: &str,
+  input_bytes: &[u8],
+  options: &super::GenerateOptions,
 ) -> Result<String, error::Error> {
-  let (main_type_str, token_tbl) = setting;
+  let ((_, main_type_str), token_tbl) = setting;
   let mut token_map = HashMap::new();
-  for (_, tokenname, typestr) in token_tbl.iter() {
+  for (_, tokenname, (_, typestr)) in token_tbl.iter() {
     token_map.insert(tokenname, typestr);
   }
   let mut fn_name_map = HashMap::new();
   for bnf in bnfs.iter() {
     let (range, name, typestr, code_vec) = match bnf {
-      types::Bnf::Pub(range, name, typestr, code_vec) => (range, name, typestr, code_vec),
-      types::Bnf::NonPub(range, name, typestr, code_vec) => (range, name, typestr, code_vec),
+      types::Bnf::Pub(range, name, (_, typestr), code_vec) => (range, name, typestr, code_vec),
+      types::Bnf::NonPub(range, name, (_, typestr), code_vec) => (range, name, typestr, code_vec),
     };
     fn_name_map.insert(name, (range, typestr, code_vec));
   }
-  let main_parse_fn_str = make_main_parse_fn_str(main_type_str.clone(), bnfs)?;
-  let parse_fn_str = make_parse_fn_str(main_type_str, &fn_name_map, &token_map, bnfs)?;
+  check_names(bnfs, &fn_name_map, &token_map)?;
+  let predict_set_map = analysis::make_predict_set_map(bnfs);
+  let main_parse_fn_str = make_main_parse_fn_str(main_type_str.clone(), bnfs, options)?;
+  let parse_fn_str = make_parse_fn_str(
+    main_type_str,
+    &fn_name_map,
+    &token_map,
+    &predict_set_map,
+    bnfs,
+    input_file_name,
+    input_bytes,
+    options,
+  )?;
   Ok(format!("{}\n{}\n", main_parse_fn_str, parse_fn_str))
 }
 
+// 規則の中で参照されている関数名とトークン名が全て定義されているかを確認する
+fn check_names(
+  bnfs: &[types::Bnf],
+  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
+  token_map: &HashMap<&String, &String>,
+) -> Result<(), error::Error> {
+  for bnf in bnfs.iter() {
+    let code_lst = match bnf {
+      types::Bnf::Pub(_, _, _, code_lst) => code_lst,
+      types::Bnf::NonPub(_, _, _, code_lst) => code_lst,
+    };
+    for (fn_or_token_lst, _) in code_lst.iter() {
+      for (_, fn_or_token) in fn_or_token_lst.iter() {
+        match fn_or_token {
+          types::FnOrToken::Function(rng, name) => {
+            if !fn_name_map.contains_key(name) {
+              return Err(not_found_function_name(*rng, name, fn_name_map));
+            }
+          }
+          types::FnOrToken::Token(rng, name) => {
+            if !token_map.contains_key(name) {
+              return Err(not_found_token_type_str(*rng, name, token_map));
+            }
+          }
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+fn not_found_function_name(
+  rng: types::Range,
+  name: &str,
+  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
+) -> error::Error {
+  let candidates: Vec<&String> = fn_name_map.keys().copied().collect();
+  error::Error::ConfigError(error::ConfigError::NotFoundFunctionName(
+    rng,
+    name.to_string(),
+    error::find_similar_name(name, &candidates),
+  ))
+}
+
+fn not_found_token_type_str(
+  rng: types::Range,
+  name: &str,
+  token_map: &HashMap<&String, &String>,
+) -> error::Error {
+  let candidates: Vec<&String> = token_map.keys().copied().collect();
+  error::Error::ConfigError(error::ConfigError::NotFoundTokenTypeStr(
+    rng,
+    name.to_string(),
+    error::find_similar_name(name, &candidates),
+  ))
+}
+
 fn make_main_parse_fn_str(
   main_type_str: String,
   bnfs: &[types::Bnf],
+  options: &super::GenerateOptions,
 ) -> Result<String, error::Error> {
   let main_fn_name_opt = bnfs
     .iter()
     .find(|bnf| matches!(bnf, types::Bnf::Pub(_, _, _, _)));
   let (main_fn_name, target_type) = match main_fn_name_opt {
-    Some(types::Bnf::Pub(_, s, ty, _)) => Ok((s, ty)),
+    Some(types::Bnf::Pub(_, s, (_, ty), _)) => Ok((s, ty)),
     _ => Err(error::Error::ConfigError(
       error::ConfigError::NotFoundPubFunctin,
     )),
   }?;
+  if options.is_trace {
+    return Ok(format!(
+      "#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+pub fn parse(tokens: Vec<{main_type}>) -> Result<{ty}, ParseError> {{
+  parse_with_tracer(tokens, &mut _NoTracer)
+}}
+
+/// Parses the tokens, sending the events of each rule and token to `tracer`.
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+pub fn parse_with_tracer(tokens: Vec<{main_type}>, {param}) -> Result<{ty}, ParseError> {{
+  let (ret, pos) = _parse_fn_{name}(&tokens, 0, tracer)?;
+  match pos.cmp(&tokens.len()) {{
+    Ordering::Equal => Ok(ret),
+    Ordering::Greater => Err(ParseError::Eof), // pos > tokens.len()
+    Ordering::Less => Err(ParseError::RedundantExpression(tokens[pos].clone()))
+  }}
+}}
+",
+      main_type = main_type_str,
+      ty = target_type,
+      name = main_fn_name,
+      param = trace::TRACER_PARAM
+    ));
+  }
   Ok(format!(
     "#[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
@@ -54,26 +158,44 @@ pub fn parse(tokens: Vec<{}>) -> Result<{}, ParseError> {{
   ))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn make_parse_fn_str(
   main_type_str: String,
   fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
   token_map: &HashMap<&String, &String>,
+  predict_set_map: &HashMap<String, Vec<BTreeSet<String>>>,
   bnfs: &[types::Bnf],
+  input_file_name: &str,
+  input_bytes: &[u8],
+  options: &super::GenerateOptions,
 ) -> Result<String, error::Error> {
   let mut main_s = String::new();
-  for v in bnfs {
+  let counter_base_lst = coverage::make_counter_base_lst(bnfs);
+  for (v, counter_base) in bnfs.iter().zip(counter_base_lst) {
     let s = match v {
-      types::Bnf::Pub(_, name, _, _) => make_parse_fn(
+      types::Bnf::Pub(rng, name, _, _) => make_parse_fn(
         main_type_str.clone(),
+        *rng,
         name.to_string(),
         fn_name_map,
         token_map,
+        predict_set_map,
+        input_file_name,
+        input_bytes,
+        options,
+        counter_base,
       )?,
-      types::Bnf::NonPub(_, name, _, _) => make_parse_fn(
+      types::Bnf::NonPub(rng, name, _, _) => make_parse_fn(
         main_type_str.clone(),
+        *rng,
         name.to_string(),
         fn_name_map,
         token_map,
+        predict_set_map,
+        input_file_name,
+        input_bytes,
+        options,
+        counter_base,
       )?,
     };
     main_s.push_str(&s)
@@ -81,21 +203,50 @@ fn make_parse_fn_str(
   Ok(main_s)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn make_parse_fn(
   main_type_str: String,
+  rng: types::Range,
   name: String,
   fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
   token_map: &HashMap<&String, &String>,
+  predict_set_map: &HashMap<String, Vec<BTreeSet<String>>>,
+  input_file_name: &str,
+  input_bytes: &[u8],
+  options: &super::GenerateOptions,
+  counter_base: usize,
 ) -> Result<String, error::Error> {
   let (_rng, type_str, code_lst) = match fn_name_map.get(&name) {
     Some((_rng, type_str, code_lst)) => Ok((_rng, type_str, code_lst)),
-    None => Err(error::Error::ConfigError(
-      error::ConfigError::NotFoundFunctionName(name.clone()),
-    )),
+    None => Err(not_found_function_name(rng, &name, fn_name_map)),
   }?;
   let code_type = make_code_type_str(code_lst);
-  let nexttoken_to_code_type = make_nexttoken_to_code_type(code_lst, fn_name_map, token_map)?;
-  let main_code_str_result = make_main_code_str(code_lst);
+  let nexttoken_to_code_type =
+    make_nexttoken_to_code_type(code_lst, &predict_set_map[&name], token_map)?;
+  let eof_to_code_type = make_eof_to_code_type(code_lst, &predict_set_map[&name]);
+  // 選択肢を選んだときに最初に実行するコード
+  let arm_prefix_lst: Vec<String> = (0..code_lst.len())
+    .map(|i| {
+      let mut s = String::new();
+      if options.is_coverage {
+        s.push_str(&format!(
+          "{}\n",
+          coverage::make_counter_code(counter_base + i)
+        ))
+      }
+      if options.is_trace {
+        s.push_str(&format!("{}\n", trace::make_choose_code(&name, i)))
+      }
+      s
+    })
+    .collect();
+  let main_code_str_result = make_main_code_str(
+    code_lst,
+    input_file_name,
+    input_bytes,
+    &arm_prefix_lst,
+    options.is_trace,
+  );
   let (main_code_str, err_or_null_code) = match main_code_str_result {
     Ok(code) => (
       code,
@@ -103,42 +254,61 @@ fn make_parse_fn(
     ),
     Err((main_code, null_code)) => (main_code, null_code),
   };
+  let location_comment = source_map::make_location_comment(input_file_name, input_bytes, &rng);
+  // トレースを使うときは、本体を別の関数にしてイベントを送る関数で包む
+  let (fn_name, tracer_param, wrapper_fn_str) = if options.is_trace {
+    (
+      format!("_parse_body_{}", name),
+      format!("\n  {},", trace::TRACER_PARAM),
+      trace::make_wrapper_fn_str(&name, &main_type_str, type_str),
+    )
+  } else {
+    (format!("_parse_fn_{}", name), String::new(), String::new())
+  };
   Ok(format!(
     "
+{}
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
-fn _parse_fn_{}(
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn {}(
   tokens: &[{}],
-  pos: usize,
+  pos: usize,{}
 ) -> Result<({}, usize), ParseError>
 {{
   let mut _token_pos = pos;
   let token1 = tokens.get(pos);
   {}
   let code_type =
-    token1
-      .ok_or(ParseError::Eof)
-      .map(|tok| match tok {{
+    match token1 {{
+      None => {{ {} }},
+      Some(tok) => match tok {{
     {}
       _ => {{CodeType::Other}}
-      }});
+      }},
+    }};
   let main =
-  match code_type? {{
+  match code_type {{
     {}
     _ => {{ {} }}
   }};
   Ok((main, _token_pos))
 }}
-",
-    name,
+{}",
+    location_comment,
+    fn_name,
     main_type_str,
+    tracer_param,
     type_str,
     code_type,
+    eof_to_code_type,
     nexttoken_to_code_type,
     main_code_str,
-    err_or_null_code
+    err_or_null_code,
+    wrapper_fn_str
   ))
 }
 
@@ -159,185 +329,95 @@ fn make_code_type_str(code_lst: &[types::Code]) -> String {
   }}", toknum_str}
 }
 
+// 各選択肢を選ぶトークンから、その選択肢へのmatchの腕を作る
+// 空の選択肢はどの選択肢にも当てはまらなかったときに使われるので、ここには含めない
 fn make_nexttoken_to_code_type(
   code_lst: &[types::Code],
-  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
+  predict_set_lst: &[BTreeSet<String>],
   token_map: &HashMap<&String, &String>,
 ) -> Result<String, error::Error> {
-  let mut tok_vec = Vec::new();
-  for (i, (v, _)) in code_lst.iter().enumerate() {
-    let next_tokens_lst = make_next_tokens_lst(&v, fn_name_map, i)?;
-    if v.is_empty() {
-    } else {
-      tok_vec.push(next_tokens_lst)
-    }
-  }
-  let mut tok_vec_old = tok_vec.concat();
-  tok_vec_old.sort();
-  let mut tok_vec = Vec::new();
-  let mut stack: Vec<Vec<usize>> = Vec::new();
-  for i in 0..tok_vec_old.len() {
-    let (fn_or_tok, v, i_vec) = &tok_vec_old[i];
-    let next_opt = &tok_vec_old.get(i + 1);
-    match next_opt {
-      None => {
-        // 最後なのでリストを回収・結合してまとめてpush
-        stack.push(i_vec.clone());
-        let i_vec_new = stack.concat();
-        tok_vec.push((fn_or_tok, v, i_vec_new));
-        stack = Vec::new();
-      }
-      Some((fn_or_tok_next, _, _)) => {
-        if fn_or_tok_next == fn_or_tok {
-          // 次のトークンは同じものなのでリストを結合して更新
-          stack.push(i_vec.clone())
-        } else {
-          // 次のトークンは違うものなのでリストを回収・結合してまとめてpush
-          stack.push(i_vec.clone());
-          let i_vec_new = stack.concat();
-          tok_vec.push((fn_or_tok, v, i_vec_new));
-          stack = Vec::new();
-        }
+  // 空の選択肢を除いた番号がCodeTypeの番号になる
+  let mut toknum_map = Vec::new();
+  let mut toknum = 0;
+  for ((fn_or_token_lst, _), predict_set) in code_lst.iter().zip(predict_set_lst.iter()) {
+    if !fn_or_token_lst.is_empty() {
+      for tokname in predict_set.iter() {
+        toknum_map.push((tokname, toknum))
       }
+      toknum += 1;
     }
   }
+  toknum_map.sort();
   let mut toknum_str = String::new();
-  for (fn_or_token, tree, i_vec) in tok_vec.iter() {
-    match fn_or_token {
-      types::FnOrToken::Token(tokname) => {
-        let s = match token_map.get(tokname) {
-          Some(s) => Ok(s.as_str()),
-          None => Err(error::Error::ConfigError(
-            error::ConfigError::NotFoundTokenTypeStr(tokname.clone()),
-          )),
-        }?;
-        let string = format!("{} => CodeType::Code{},\n", s, i_vec[0]);
-        println!("{:?}: {:?}", fn_or_token, tree);
-        toknum_str.push_str(&string)
-      }
-      types::FnOrToken::Function(_) => (),
+  for (tokname, toknum) in toknum_map.iter() {
+    if tokname.as_str() == analysis::END_OF_INPUT {
+      continue;
     }
+    let s = match token_map.get(tokname) {
+      Some(s) => Ok(s.as_str()),
+      None => Err(not_found_token_type_str(
+        types::Range::dummy(),
+        tokname,
+        token_map,
+      )),
+    }?;
+    toknum_str.push_str(&format!("{} => CodeType::Code{},\n", s, toknum))
   }
   Ok(toknum_str)
 }
 
-#[allow(clippy::type_complexity)]
-fn make_next_tokens_lst(
-  tokens_lst: &[(String, types::FnOrToken)],
-  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
-  i: usize,
-) -> Result<Vec<(types::FnOrToken, Vec<Vec<usize>>, Vec<usize>)>, error::Error> {
-  match tokens_lst.iter().next() {
-    None => Ok(Vec::new()),
-    Some((_, fn_or_token)) => {
-      let lst = serch_next_token(
-        &[(fn_or_token.clone(), vec![vec![i]])],
-        fn_name_map,
-        vec![vec![i]],
-      )?;
-      let mut v = Vec::new();
-      for (ft, tree) in lst.iter() {
-        v.push((ft.clone(), tree.clone(), vec![i]))
+// 入力の終わりで選ぶ選択肢
+// 入力の終わりから選ばれる空でない選択肢があればそれを、無ければ空の選択肢を使い、
+// 空の選択肢も無ければEofを返す
+fn make_eof_to_code_type(code_lst: &[types::Code], predict_set_lst: &[BTreeSet<String>]) -> String {
+  let mut toknum = 0;
+  for ((fn_or_token_lst, _), predict_set) in code_lst.iter().zip(predict_set_lst.iter()) {
+    if !fn_or_token_lst.is_empty() {
+      if predict_set.contains(analysis::END_OF_INPUT) {
+        return format!("CodeType::Code{}", toknum);
       }
-      Ok(v)
+      toknum += 1;
     }
   }
-}
-
-#[allow(clippy::type_complexity)]
-fn serch_next_token(
-  fn_or_token_lst: &[(types::FnOrToken, Vec<Vec<usize>>)],
-  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
-  i_vec: Vec<Vec<usize>>,
-) -> Result<Vec<(types::FnOrToken, Vec<Vec<usize>>)>, error::Error> {
-  // fn_or_token_lstにserchをmapしてリストを作る
-  // それらをconcatして重複を取り除く
-  // 長さが元のfn_or_token_lstと変わらなかったらループに入っているので処理を終了し、
-  // Tokenだけを残す処理をして、処理後のリストを返す
-  // 長さが変化していたら処理が継続中なのでserch_next_tokenに値を入れて再帰化
-  let new_fn_or_token_lst_lst_res: Vec<
-    Result<Vec<(types::FnOrToken, Vec<Vec<usize>>)>, error::Error>,
-  > = fn_or_token_lst
+  if code_lst
     .iter()
-    .map(|(fn_or_token, tree)| serch(fn_or_token, tree, fn_name_map))
-    .collect();
-  let mut old_fn_or_token_lst = fn_or_token_lst.to_owned();
-  let mut new_fn_or_token_lst_lst = Vec::new();
-  // Errが無い限り突っ込んでいく
-  // Errがあったらそこで終了
-  for v in new_fn_or_token_lst_lst_res.iter() {
-    match v {
-      Ok(fn_or_token_lst) => new_fn_or_token_lst_lst.push(fn_or_token_lst.clone()),
-      Err(e) => return Err(e.clone()),
-    }
-  }
-  let mut new_fn_or_token_lst = new_fn_or_token_lst_lst.concat();
-  // 元のリストと結合
-  new_fn_or_token_lst.append(&mut old_fn_or_token_lst);
-  // sortして揃える
-  new_fn_or_token_lst.sort();
-  // 次の中身が同じならi_vecをstack
-  // 次の中身が違うならstackの中身を取り出す
-  new_fn_or_token_lst.dedup();
-  // 長さを見る
-  if fn_or_token_lst.len() == new_fn_or_token_lst.len() {
-    Ok(new_fn_or_token_lst)
+    .any(|(fn_or_token_lst, _)| fn_or_token_lst.is_empty())
+  {
+    "CodeType::Other".to_string()
   } else {
-    serch_next_token(&new_fn_or_token_lst, fn_name_map, i_vec)
-  }
-}
-
-#[allow(clippy::type_complexity)]
-fn serch(
-  fn_or_token: &types::FnOrToken,
-  tree: &[Vec<usize>],
-  fn_name_map: &HashMap<&String, (&types::Range, &String, &Vec<types::Code>)>,
-) -> Result<Vec<(types::FnOrToken, Vec<Vec<usize>>)>, error::Error> {
-  fn get_head(
-    lst: &[types::Code],
-    tree: &[Vec<usize>],
-  ) -> Vec<(types::FnOrToken, Vec<Vec<usize>>)> {
-    let mut main_vec = Vec::new();
-    for (new_fn_or_token_lst, _) in lst.iter() {
-      match new_fn_or_token_lst.iter().next() {
-        None => (),
-        Some((_, fn_or_token)) => main_vec.push((fn_or_token.clone(), tree.to_vec())),
-      }
-    }
-    main_vec
-  };
-  match fn_or_token {
-    types::FnOrToken::Token(_) => Ok(vec![(fn_or_token.clone(), tree.to_vec())]),
-    types::FnOrToken::Function(s) => {
-      let code_lst = match fn_name_map.get(s) {
-        Some((_, _, code_lst)) => Ok(code_lst),
-        None => Err(error::Error::ConfigError(
-          error::ConfigError::NotFoundFunctionName(s.clone()),
-        )),
-      }?;
-      Ok(get_head(code_lst, tree))
-    }
+    "return Err(ParseError::Eof)".to_string()
   }
 }
 
 // nullが無ければコードを全部結合した文字列を
 // nullがあったらnull以外のコードを結合した文字列とnullの場合のコードを返す。
-fn make_main_code_str(code_lst: &[types::Code]) -> Result<String, (String, String)> {
+// 各選択肢のコードの先頭には、arm_prefix_lstの対応するコードを入れる
+fn make_main_code_str(
+  code_lst: &[types::Code],
+  input_file_name: &str,
+  input_bytes: &[u8],
+  arm_prefix_lst: &[String],
+  is_trace: bool,
+) -> Result<String, (String, String)> {
   let mut null_code_opt = None;
   let mut code_str = String::new();
   let mut toknum = 0;
-  for (fn_or_token_lst, code) in code_lst.iter() {
+  for (i, (fn_or_token_lst, (code_rng, code))) in code_lst.iter().enumerate() {
+    let location_comment =
+      source_map::make_location_comment(input_file_name, input_bytes, code_rng);
+    let arm_prefix = &arm_prefix_lst[i];
     if fn_or_token_lst.is_empty() {
-      null_code_opt = Some(code.to_string())
+      null_code_opt = Some(format!("\n{}{}\n{}", arm_prefix, location_comment, code))
     } else {
-      let let_code = make_let_code(fn_or_token_lst);
+      let let_code = make_let_code(fn_or_token_lst, is_trace);
       code_str.push_str(&format!(
         "CodeType::Code{} => {{
-{}
+{}{}
       _token_pos = pos;
+{}
 {}
         }}",
-        toknum, let_code, code
+        toknum, arm_prefix, let_code, location_comment, code
       ));
       toknum += 1;
     }
@@ -348,20 +428,51 @@ fn make_main_code_str(code_lst: &[types::Code]) -> Result<String, (String, Strin
   }
 }
 
-fn make_let_code(fn_or_token_lst: &[(String, types::FnOrToken)]) -> String {
+fn make_let_code(fn_or_token_lst: &[(String, types::FnOrToken)], is_trace: bool) -> String {
   let mut main_s = String::new();
+  let args = if is_trace {
+    "tokens, pos, tracer"
+  } else {
+    "tokens, pos"
+  };
   for (name, fn_or_token) in fn_or_token_lst.iter() {
     let s = match fn_or_token {
-      types::FnOrToken::Function(fn_name) => format!(
-        "      let ({}, pos) = _parse_fn_{}(tokens, pos)?;\n",
-        name, fn_name
+      types::FnOrToken::Function(_, fn_name) => format!(
+        "      let ({}, pos) = _parse_fn_{}({})?;\n",
+        name, fn_name, args
       ),
-      types::FnOrToken::Token(tok_name) => format!(
-        "      let ({}, pos) = _parse_token_{}(tokens, pos)?;\n",
-        name, tok_name
+      types::FnOrToken::Token(_, tok_name) => format!(
+        "      let ({}, pos) = _parse_token_{}({})?;\n",
+        name, tok_name, args
       ),
     };
     main_s.push_str(&s)
   }
   main_s
 }
+
+#[test]
+fn check_make_nexttoken_to_code_type() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+    Tok_B => \"'b'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: opt> <_b: Tok_B> => {\"()\"},
+};
+opt: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let parse_fn_fn_str = super::to_string(term, "test.mkr", input).unwrap();
+  // optが空になりうるので、mainの選択肢はoptの後のTok_Bでも選ばれる
+  assert!(parse_fn_fn_str.contains("'a' => CodeType::Code0,\n'b' => CodeType::Code0,\n"));
+  // 入力の終わりでは、optは空の選択肢を選び、mainはEofを返す
+  assert!(parse_fn_fn_str.contains("None => { CodeType::Other },"));
+  assert!(parse_fn_fn_str.contains("None => { return Err(ParseError::Eof) },"));
+}
@@ -1,15 +1,51 @@
+use super::trace;
 use super::types;
 
-pub fn make_parse_token_fn_str(setting: types::Setting) -> String {
-  let (main_type_str, token_and_str_vec) = setting;
-  token_and_str_vec_to_str(main_type_str, token_and_str_vec)
+pub fn make_parse_token_fn_str(
+  setting: types::Setting,
+  options: &super::GenerateOptions,
+) -> String {
+  let ((_, main_type_str), token_and_str_vec) = setting;
+  token_and_str_vec_to_str(main_type_str, token_and_str_vec, options.is_trace)
 }
 
 fn token_and_str_to_str(
   main_type_str: String,
   token_and_str: (types::Range, String, types::TypeStr),
+  is_trace: bool,
 ) -> String {
-  let (_, token_name, type_str) = token_and_str;
+  let (_, token_name, (_, type_str)) = token_and_str;
+  if is_trace {
+    return format!(
+      "
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+fn _parse_token_{name}(
+  tokens: &[{main_type}],
+  pos: usize,
+  {param},
+) -> Result<({main_type}, usize), ParseError>
+{{
+  tracer.enter_token({name:?}, pos);
+  let token1 = tokens.get(pos);
+  let result = token1
+  .ok_or(ParseError::Eof)
+  .and_then(|tok| match tok {{
+    {pattern} => Ok((tok.clone(), pos + 1)),
+    _ => Err(ParseError::UnexpectedToken(tok.clone())),
+  }});
+  tracer.exit_token({name:?}, pos, result.is_ok());
+  result
+}}
+",
+      name = token_name,
+      main_type = main_type_str,
+      param = trace::TRACER_PARAM,
+      pattern = type_str
+    );
+  }
   format!(
     "
 #[allow(non_camel_case_types)]
@@ -37,10 +73,11 @@ fn _parse_token_{}(
 fn token_and_str_vec_to_str(
   main_type_str: String,
   token_and_str_vec: Vec<(types::Range, String, types::TypeStr)>,
+  is_trace: bool,
 ) -> String {
   let mut main_s = String::new();
   for v in token_and_str_vec.iter() {
-    let s = token_and_str_to_str(main_type_str.clone(), v.clone());
+    let s = token_and_str_to_str(main_type_str.clone(), v.clone(), is_trace);
     main_s.push_str(&s)
   }
   main_s
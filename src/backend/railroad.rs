@@ -0,0 +1,264 @@
+use super::types;
+
+// 各規則の鉄道図（構文図）をSVGで描き、1つのHTMLにまとめて出力する
+// トークンは角の丸い枠、規則は四角い枠で表し、規則の枠はその規則の図へのリンクにする
+
+enum Item {
+  Terminal(String),
+  NonTerminal(String),
+  Sequence(Vec<Item>),
+  Choice(Vec<Item>),
+}
+
+// 曲線の半径
+const ARC: i32 = 10;
+// 並んだ要素の間の横の隙間
+const GAP: i32 = 10;
+// 選択肢の間の縦の隙間
+const VGAP: i32 = 10;
+// 枠の高さの半分
+const BOX_HALF_HEIGHT: i32 = 11;
+// 1文字あたりの幅
+const CHAR_WIDTH: i32 = 8;
+const PADDING: i32 = 20;
+
+fn make_item(code_lst: &[types::Code]) -> Item {
+  let sequences: Vec<Item> = code_lst
+    .iter()
+    .map(|(fn_or_token_lst, _)| {
+      Item::Sequence(
+        fn_or_token_lst
+          .iter()
+          .map(|(_, fn_or_token)| match fn_or_token {
+            types::FnOrToken::Token(_, name) => Item::Terminal(name.clone()),
+            types::FnOrToken::Function(_, name) => Item::NonTerminal(name.clone()),
+          })
+          .collect(),
+      )
+    })
+    .collect();
+  if sequences.len() == 1 {
+    sequences.into_iter().next().unwrap()
+  } else {
+    Item::Choice(sequences)
+  }
+}
+
+// 要素の（幅、線より上の高さ、線より下の高さ）
+fn get_size(item: &Item) -> (i32, i32, i32) {
+  match item {
+    Item::Terminal(name) | Item::NonTerminal(name) => (
+      name.chars().count() as i32 * CHAR_WIDTH + 2 * GAP,
+      BOX_HALF_HEIGHT,
+      BOX_HALF_HEIGHT,
+    ),
+    Item::Sequence(items) => {
+      let sizes: Vec<(i32, i32, i32)> = items.iter().map(get_size).collect();
+      let width =
+        sizes.iter().map(|(w, _, _)| w).sum::<i32>() + GAP * (sizes.len() as i32 - 1).max(0);
+      let up = sizes.iter().map(|(_, up, _)| *up).max().unwrap_or(0);
+      let down = sizes.iter().map(|(_, _, down)| *down).max().unwrap_or(0);
+      (width, up, down)
+    }
+    Item::Choice(items) => {
+      let sizes: Vec<(i32, i32, i32)> = items.iter().map(get_size).collect();
+      let width = sizes.iter().map(|(w, _, _)| *w).max().unwrap_or(0) + 4 * ARC;
+      let up = sizes.first().map(|(_, up, _)| *up).unwrap_or(0);
+      let down = get_choice_offsets(&sizes).last().copied().unwrap_or(0)
+        + sizes.last().map(|(_, _, down)| *down).unwrap_or(0);
+      (width, up, down)
+    }
+  }
+}
+
+// 選択肢ごとの、最初の選択肢の線からの縦の距離
+fn get_choice_offsets(sizes: &[(i32, i32, i32)]) -> Vec<i32> {
+  let mut offsets = Vec::new();
+  let mut y = 0;
+  for (i, (_, up, _)) in sizes.iter().enumerate() {
+    if i > 0 {
+      let (_, _, prev_down) = sizes[i - 1];
+      y += (prev_down + VGAP + up).max(2 * ARC);
+    }
+    offsets.push(y)
+  }
+  offsets
+}
+
+fn escape_xml_str(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn push_line(s: &mut String, x1: i32, x2: i32, y: i32) {
+  if x1 != x2 {
+    s.push_str(&format!("<path d=\"M{} {} H{}\"/>\n", x1, y, x2))
+  }
+}
+
+// 線の左端が(x, y)になるように要素を描く
+fn render(item: &Item, x: i32, y: i32, s: &mut String) {
+  match item {
+    Item::Terminal(name) | Item::NonTerminal(name) => {
+      let (width, _, _) = get_size(item);
+      let text = format!(
+        "<text x=\"{}\" y=\"{}\">{}</text>",
+        x + width / 2,
+        y + 4,
+        escape_xml_str(name)
+      );
+      match item {
+        Item::Terminal(_) => s.push_str(&format!(
+          "<g class=\"terminal\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>{}</g>\n",
+          x,
+          y - BOX_HALF_HEIGHT,
+          width,
+          2 * BOX_HALF_HEIGHT,
+          BOX_HALF_HEIGHT,
+          text
+        )),
+        _ => s.push_str(&format!(
+          "<a href=\"#rule-{}\"><g class=\"nonterminal\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>{}</g></a>\n",
+          escape_xml_str(name),
+          x,
+          y - BOX_HALF_HEIGHT,
+          width,
+          2 * BOX_HALF_HEIGHT,
+          text
+        )),
+      }
+    }
+    Item::Sequence(items) => {
+      let mut cur_x = x;
+      for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+          push_line(s, cur_x, cur_x + GAP, y);
+          cur_x += GAP;
+        }
+        render(item, cur_x, y, s);
+        let (width, _, _) = get_size(item);
+        cur_x += width;
+      }
+    }
+    Item::Choice(items) => {
+      let (width, _, _) = get_size(item);
+      let sizes: Vec<(i32, i32, i32)> = items.iter().map(get_size).collect();
+      let offsets = get_choice_offsets(&sizes);
+      let inner_x = x + 2 * ARC;
+      let inner_right = x + width - 2 * ARC;
+      for ((item, (item_width, _, _)), offset) in items.iter().zip(sizes.iter()).zip(offsets) {
+        let item_y = y + offset;
+        if offset == 0 {
+          push_line(s, x, inner_x, y);
+          push_line(s, inner_right, x + width, y);
+        } else {
+          s.push_str(&format!(
+            "<path d=\"M{} {} a{r} {r} 0 0 1 {r} {r} V{} a{r} {r} 0 0 0 {r} {r}\"/>\n",
+            x,
+            y,
+            item_y - ARC,
+            r = ARC
+          ));
+          s.push_str(&format!(
+            "<path d=\"M{} {} a{r} {r} 0 0 0 {r} -{r} V{} a{r} {r} 0 0 1 {r} -{r}\"/>\n",
+            inner_right,
+            item_y,
+            y + ARC,
+            r = ARC
+          ));
+        }
+        render(item, inner_x, item_y, s);
+        push_line(s, inner_x + item_width, inner_right, item_y);
+      }
+    }
+  }
+}
+
+fn make_svg_str(item: &Item) -> String {
+  let (width, up, down) = get_size(item);
+  let svg_width = width + 2 * PADDING + 2 * GAP;
+  let svg_height = up + down + 2 * PADDING;
+  let y = PADDING + up;
+  let mut s = format!(
+    "<svg class=\"railroad\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+    svg_width, svg_height, svg_width, svg_height
+  );
+  // 始まりと終わりの印
+  s.push_str(&format!(
+    "<path d=\"M{} {} v20 M{} {} v20\"/>\n",
+    PADDING,
+    y - 10,
+    svg_width - PADDING,
+    y - 10
+  ));
+  push_line(&mut s, PADDING, PADDING + GAP, y);
+  render(item, PADDING + GAP, y, &mut s);
+  push_line(&mut s, PADDING + GAP + width, svg_width - PADDING, y);
+  s.push_str("</svg>\n");
+  s
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; }
+svg.railroad path { stroke: #333; stroke-width: 2; fill: none; }
+svg.railroad rect { stroke: #333; stroke-width: 2; fill: #ffc; }
+svg.railroad .nonterminal rect { fill: #cef; }
+svg.railroad text { font: 13px monospace; text-anchor: middle; }
+";
+
+pub fn make_railroad_str(term: &types::Term) -> String {
+  let (_, _, bnfs) = term;
+  let mut s = String::new();
+  s.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+  s.push_str(&format!("<style>{}</style>\n", STYLE));
+  s.push_str("</head>\n<body>\n");
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    let name = escape_xml_str(name);
+    s.push_str(&format!("<h2 id=\"rule-{}\">{}</h2>\n", name, name));
+    s.push_str(&make_svg_str(&make_item(code_lst)));
+  }
+  s.push_str("</body>\n</html>\n");
+  s
+}
+
+#[test]
+fn check_make_railroad_str() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: sub> <_a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+sub: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let railroad_str = make_railroad_str(&term);
+  assert!(railroad_str.starts_with("<!DOCTYPE html>\n"));
+  assert!(railroad_str.contains("<h2 id=\"rule-main\">main</h2>\n"));
+  assert!(railroad_str.contains("<h2 id=\"rule-sub\">sub</h2>\n"));
+  assert_eq!(railroad_str.matches("<svg class=\"railroad\"").count(), 2);
+  // 規則は図へのリンクになり、トークンは角の丸い枠になる
+  assert_eq!(railroad_str.matches("<a href=\"#rule-sub\">").count(), 1);
+  assert_eq!(
+    railroad_str
+      .matches("<g class=\"terminal\"><rect x=\"")
+      .count(),
+    2
+  );
+  assert!(railroad_str.contains("height=\"22\" rx=\"11\"/><text x="));
+  // mainは2つの選択肢なので、2つ目の選択肢への分岐と合流の曲線がある
+  assert_eq!(railroad_str.matches(" a10 10 0 0 1 10 10 V").count(), 1);
+  assert_eq!(railroad_str.matches(" a10 10 0 0 0 10 -10 V").count(), 1);
+}
@@ -0,0 +1,277 @@
+use super::error;
+use super::interpreter;
+use super::types;
+use std::collections::HashMap;
+
+// 文法からランダムなトークン名の列を作る（下流のパーサのファジング用）
+// 各規則について、導出木の深さの最小値を求めておき、
+// 残りの深さで導出を終えられる選択肢だけから選ぶことで、必ず有限の列になるようにする
+// 空の選択肢は他の選択肢に当てはまらないときにだけ使われるので、導出した列でもパーサが読めないことがある
+// そのため、作った列はinterpreterで読めるか確かめてから使う
+
+// 1つの列を作るために試す回数の上限
+const MAX_ATTEMPTS_PER_SAMPLE: usize = 100;
+
+/// 列を作るときの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleOptions {
+  /// 作る列の数
+  pub count: usize,
+  /// 導出木の深さの上限。規則の導出に必要な最小の深さより小さい場合は、最小の深さを使う
+  pub max_depth: usize,
+  pub seed: u64,
+  /// trueなら、読める列を少しだけ書き換えた、読めない列を作る
+  pub is_near_miss: bool,
+}
+
+// 乱数（xorshift64*）
+struct Rng(u64);
+
+impl Rng {
+  fn new(seed: u64) -> Rng {
+    // 近い種から似た列ができないように、splitmix64で混ぜる
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    Rng(if z == 0 { 1 } else { z })
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+  }
+
+  // 0以上n未満の数
+  fn below(&mut self, n: usize) -> usize {
+    (self.next_u64() % n as u64) as usize
+  }
+}
+
+fn get_alternative_depth(
+  fn_or_token_lst: &[(String, types::FnOrToken)],
+  min_depth_map: &HashMap<String, usize>,
+) -> Option<usize> {
+  let mut depth = 1;
+  for (_, fn_or_token) in fn_or_token_lst.iter() {
+    if let types::FnOrToken::Function(_, fn_name) = fn_or_token {
+      depth = depth.max(min_depth_map.get(fn_name)? + 1)
+    }
+  }
+  Some(depth)
+}
+
+/// 各規則について、導出木の深さの最小値を求める
+///
+/// 有限の列を導出できない規則は含まれない。
+pub fn make_min_depth_map(bnfs: &[types::Bnf]) -> HashMap<String, usize> {
+  let mut min_depth_map: HashMap<String, usize> = HashMap::new();
+  // 変化が無くなるまで繰り返す
+  let mut is_changed = true;
+  while is_changed {
+    is_changed = false;
+    for bnf in bnfs.iter() {
+      let (name, code_lst) = match bnf {
+        types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+        types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+      };
+      let depth_opt = code_lst
+        .iter()
+        .filter_map(|(fn_or_token_lst, _)| get_alternative_depth(fn_or_token_lst, &min_depth_map))
+        .min();
+      if let Some(depth) = depth_opt {
+        let is_smaller = match min_depth_map.get(name) {
+          Some(d) => depth < *d,
+          None => true,
+        };
+        if is_smaller {
+          min_depth_map.insert(name.clone(), depth);
+          is_changed = true
+        }
+      }
+    }
+  }
+  min_depth_map
+}
+
+struct Generator<'a> {
+  code_lst_map: HashMap<&'a String, &'a Vec<types::Code>>,
+  min_depth_map: HashMap<String, usize>,
+  rng: Rng,
+}
+
+impl<'a> Generator<'a> {
+  // 深さdepthまでで導出を終えられる選択肢から1つ選び、トークン名を付け足していく
+  fn generate_rule(&mut self, name: &str, depth: usize, tokens: &mut Vec<String>) {
+    let code_lst = self.code_lst_map[&name.to_string()];
+    let candidate_lst: Vec<&Vec<(String, types::FnOrToken)>> = code_lst
+      .iter()
+      .map(|(fn_or_token_lst, _)| fn_or_token_lst)
+      .filter(|fn_or_token_lst| {
+        matches!(get_alternative_depth(fn_or_token_lst, &self.min_depth_map), Some(d) if d <= depth)
+      })
+      .collect();
+    let fn_or_token_lst = candidate_lst[self.rng.below(candidate_lst.len())];
+    for (_, fn_or_token) in fn_or_token_lst.iter() {
+      match fn_or_token {
+        types::FnOrToken::Token(_, tok_name) => tokens.push(tok_name.clone()),
+        types::FnOrToken::Function(_, fn_name) => self.generate_rule(fn_name, depth - 1, tokens),
+      }
+    }
+  }
+}
+
+// 1か所だけ、トークンを消す・入れる・置き換える・隣と入れ替える
+fn mutate(tokens: &[String], token_name_lst: &[&String], rng: &mut Rng) -> Vec<String> {
+  let mut mutated = tokens.to_vec();
+  let kind = if mutated.is_empty() { 1 } else { rng.below(4) };
+  match kind {
+    0 => {
+      mutated.remove(rng.below(mutated.len()));
+    }
+    1 => {
+      let tok_name = token_name_lst[rng.below(token_name_lst.len())].clone();
+      mutated.insert(rng.below(mutated.len() + 1), tok_name)
+    }
+    2 => {
+      let i = rng.below(mutated.len());
+      mutated[i] = token_name_lst[rng.below(token_name_lst.len())].clone()
+    }
+    _ => {
+      if mutated.len() > 1 {
+        let i = rng.below(mutated.len() - 1);
+        mutated.swap(i, i + 1)
+      }
+    }
+  }
+  mutated
+}
+
+/// `rule_name`の規則から、ランダムなトークン名の列を作る
+///
+/// 作った列は全て、`interpreter::parse`で読める（`is_near_miss`がtrueなら読めない）ことを確かめてある。
+/// `interpreter::parse`は入力の終わりも含めて生成されるパーサと同じように選択肢を選ぶので、生成されるパーサでも同じ結果になる。
+/// 試す回数には上限があるので、`count`より少ない数の列しか返らないことがある。
+pub fn generate_samples(
+  term: &types::Term,
+  rule_name: &str,
+  options: &SampleOptions,
+) -> Result<Vec<Vec<String>>, error::Error> {
+  let (_, (_, token_lst), bnfs) = term;
+  if !interpreter::is_defined_rule(term, rule_name) {
+    return Err(error::Error::SampleError(error::SampleError::NotFoundRule(
+      rule_name.to_string(),
+    )));
+  }
+  let min_depth_map = make_min_depth_map(bnfs);
+  let depth = match min_depth_map.get(rule_name) {
+    Some(min_depth) => options.max_depth.max(*min_depth),
+    None => {
+      return Err(error::Error::SampleError(
+        error::SampleError::NoFiniteDerivation(rule_name.to_string()),
+      ))
+    }
+  };
+  let mut code_lst_map = HashMap::new();
+  for bnf in bnfs.iter() {
+    match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) | types::Bnf::NonPub(_, name, _, code_lst) => {
+        code_lst_map.insert(name, code_lst)
+      }
+    };
+  }
+  let token_name_lst: Vec<&String> = token_lst.iter().map(|(_, name, _)| name).collect();
+  let mut generator = Generator {
+    code_lst_map,
+    min_depth_map,
+    rng: Rng::new(options.seed),
+  };
+  let mut sample_lst = Vec::new();
+  for _ in 0..options.count.saturating_mul(MAX_ATTEMPTS_PER_SAMPLE) {
+    if sample_lst.len() == options.count {
+      break;
+    }
+    let mut tokens = Vec::new();
+    generator.generate_rule(rule_name, depth, &mut tokens);
+    if interpreter::parse(term, rule_name, &tokens).is_err() {
+      continue;
+    }
+    if options.is_near_miss {
+      if token_name_lst.is_empty() {
+        break;
+      }
+      let mutated = mutate(&tokens, &token_name_lst, &mut generator.rng);
+      if interpreter::parse(term, rule_name, &mutated).is_ok() {
+        continue;
+      }
+      tokens = mutated
+    }
+    sample_lst.push(tokens)
+  }
+  Ok(sample_lst)
+}
+
+#[test]
+fn check_generate_samples() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_NUM => \"Token::Num\",
+    Tok_PLUS => \"Token::Plus\",
+    Tok_LPAREN => \"Token::LParen\",
+    Tok_RPAREN => \"Token::RParen\",
+  }
+}
+pub expr: \"()\" = {
+  <_a: atom> <_t: expr_tail> => {\"()\"},
+};
+expr_tail: \"()\" = {
+  <_p: Tok_PLUS> <_e: expr> => {\"()\"},
+  => {\"()\"},
+};
+atom: \"()\" = {
+  <_n: Tok_NUM> => {\"()\"},
+  <_l: Tok_LPAREN> <_e: expr> <_r: Tok_RPAREN> => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let (_, _, bnfs) = &term;
+  let min_depth_map = make_min_depth_map(bnfs);
+  assert_eq!(min_depth_map["expr_tail"], 1);
+  assert_eq!(min_depth_map["atom"], 1);
+  assert_eq!(min_depth_map["expr"], 2);
+  let mut options = SampleOptions {
+    count: 20,
+    max_depth: 6,
+    seed: 1,
+    is_near_miss: false,
+  };
+  let sample_lst = generate_samples(&term, "expr", &options).unwrap();
+  assert_eq!(sample_lst.len(), 20);
+  assert_eq!(
+    sample_lst,
+    generate_samples(&term, "expr", &options).unwrap()
+  );
+  for tokens in sample_lst.iter() {
+    assert!(interpreter::parse(&term, "expr", tokens).is_ok());
+    // 深さ6までなので、括弧の入れ子は2段まで
+    let mut nest = 0;
+    for tok_name in tokens.iter() {
+      match tok_name.as_str() {
+        "Tok_LPAREN" => nest += 1,
+        "Tok_RPAREN" => nest -= 1,
+        _ => (),
+      }
+      assert!(nest <= 2);
+    }
+  }
+  // 入力の終わりでexpr_tailが空になる列も作る（生成されるパーサもこれを読む）
+  assert!(sample_lst.contains(&vec!["Tok_NUM".to_string()]));
+  options.is_near_miss = true;
+  for tokens in generate_samples(&term, "expr", &options).unwrap().iter() {
+    assert!(interpreter::parse(&term, "expr", tokens).is_err());
+  }
+  assert!(generate_samples(&term, "none", &options).is_err());
+}
@@ -0,0 +1,256 @@
+use super::error;
+use super::types;
+
+// 型・パターン・コードの文字列がRustとして正しいかを確認する
+// syn featureが無効な場合は括弧の対応だけを確認する
+pub fn check_snippets(setting: &types::Setting, bnfs: &[types::Bnf]) -> Result<(), error::Error> {
+  let (main_type_str, token_tbl) = setting;
+  check_type(main_type_str)?;
+  for (_, _, pattern_str) in token_tbl.iter() {
+    check_pattern(pattern_str)?;
+  }
+  for bnf in bnfs.iter() {
+    let (type_str, code_lst) = match bnf {
+      types::Bnf::Pub(_, _, type_str, code_lst) => (type_str, code_lst),
+      types::Bnf::NonPub(_, _, type_str, code_lst) => (type_str, code_lst),
+    };
+    check_type(type_str)?;
+    for (_, code_str) in code_lst.iter() {
+      check_code(code_str)?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(feature = "syn")]
+fn check_type(type_str: &types::TypeStr) -> Result<(), error::Error> {
+  let (rng, s) = type_str;
+  match syn::parse_str::<syn::Type>(s) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(error::Error::ConfigError(
+      error::ConfigError::InvalidTypeStr(*rng, e.to_string()),
+    )),
+  }
+}
+
+#[cfg(feature = "syn")]
+fn check_pattern(pattern_str: &types::TypeStr) -> Result<(), error::Error> {
+  let (rng, s) = pattern_str;
+  // `A | B`のようなパターンも許すためにmatchの腕として読む
+  match syn::parse_str::<syn::Arm>(&format!("{} => ()", s)) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(error::Error::ConfigError(
+      error::ConfigError::InvalidTokenPattern(*rng, e.to_string()),
+    )),
+  }
+}
+
+#[cfg(feature = "syn")]
+fn check_code(code_str: &types::CodeStr) -> Result<(), error::Error> {
+  let (rng, s) = code_str;
+  match syn::parse_str::<syn::Block>(&format!("{{{}}}", s)) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(error::Error::ConfigError(error::ConfigError::InvalidCode(
+      *rng,
+      e.to_string(),
+    ))),
+  }
+}
+
+#[cfg(not(feature = "syn"))]
+fn check_type(type_str: &types::TypeStr) -> Result<(), error::Error> {
+  let (rng, s) = type_str;
+  check_delimiters(s)
+    .map_err(|msg| error::Error::ConfigError(error::ConfigError::InvalidTypeStr(*rng, msg)))
+}
+
+#[cfg(not(feature = "syn"))]
+fn check_pattern(pattern_str: &types::TypeStr) -> Result<(), error::Error> {
+  let (rng, s) = pattern_str;
+  check_delimiters(s)
+    .map_err(|msg| error::Error::ConfigError(error::ConfigError::InvalidTokenPattern(*rng, msg)))
+}
+
+#[cfg(not(feature = "syn"))]
+fn check_code(code_str: &types::CodeStr) -> Result<(), error::Error> {
+  let (rng, s) = code_str;
+  check_delimiters(s)
+    .map_err(|msg| error::Error::ConfigError(error::ConfigError::InvalidCode(*rng, msg)))
+}
+
+fn is_ident_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
+
+/// 括弧の対応が取れているかを確認する
+///
+/// syn featureが無効なときの最低限の検査と、llmaker-macrosでコードを`TokenStream`にする前の検査に使う。
+/// 文字列・文字・コメントの中の括弧は数えない。
+pub fn check_delimiters(s: &str) -> Result<(), String> {
+  let chars: Vec<char> = s.chars().collect();
+  let mut stack = Vec::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    let next_opt = chars.get(i + 1).copied();
+    match c {
+      '(' | '[' | '{' => stack.push(c),
+      ')' | ']' | '}' => {
+        let open = match c {
+          ')' => '(',
+          ']' => '[',
+          _ => '{',
+        };
+        match stack.pop() {
+          Some(c2) if c2 == open => (),
+          Some(c2) => return Err(format!("mismatched closing delimiter `{}` for `{}`", c, c2)),
+          None => return Err(format!("unexpected closing delimiter `{}`", c)),
+        }
+      }
+      '/' if next_opt == Some('/') => {
+        while i < chars.len() && chars[i] != '\n' {
+          i += 1
+        }
+      }
+      '/' if next_opt == Some('*') => {
+        // ブロックコメントは入れ子になる
+        let mut depth = 0;
+        loop {
+          match (chars.get(i), chars.get(i + 1)) {
+            (Some('/'), Some('*')) => {
+              depth += 1;
+              i += 1
+            }
+            (Some('*'), Some('/')) => {
+              depth -= 1;
+              i += 1;
+              if depth == 0 {
+                break;
+              }
+            }
+            (None, _) => return Err("unterminated block comment".to_string()),
+            _ => (),
+          }
+          i += 1
+        }
+      }
+      '"' => {
+        i += 1;
+        loop {
+          match chars.get(i) {
+            Some('\\') => i += 1,
+            Some('"') => break,
+            None => return Err("unterminated double quote string".to_string()),
+            _ => (),
+          }
+          i += 1
+        }
+      }
+      // r"..."、r#"..."#、br"..."
+      'r'
+        if i == 0
+          || !is_ident_char(chars[i - 1])
+          || (chars[i - 1] == 'b' && (i == 1 || !is_ident_char(chars[i - 2]))) =>
+      {
+        let hash_len = chars[i + 1..].iter().take_while(|c| **c == '#').count();
+        if chars.get(i + 1 + hash_len) == Some(&'"') {
+          i += hash_len + 2;
+          loop {
+            if i >= chars.len() {
+              return Err("unterminated raw string".to_string());
+            }
+            if chars[i] == '"'
+              && chars[i + 1..]
+                .iter()
+                .take(hash_len)
+                .filter(|c| **c == '#')
+                .count()
+                == hash_len
+            {
+              i += hash_len;
+              break;
+            }
+            i += 1
+          }
+        }
+      }
+      // 文字リテラル（ライフタイムの`'`は読み飛ばさない）
+      '\'' => {
+        if next_opt == Some('\\') {
+          i += 2;
+          while i < chars.len() && chars[i] != '\'' {
+            i += 1
+          }
+        } else if chars.get(i + 2) == Some(&'\'') {
+          i += 2
+        }
+      }
+      _ => (),
+    }
+    i += 1
+  }
+  match stack.last() {
+    Some(c) => Err(format!("unclosed delimiter `{}`", c)),
+    None => Ok(()),
+  }
+}
+
+#[test]
+fn check_check_delimiters() {
+  assert!(check_delimiters("let v = vec![(1, 2)];\n{ v }").is_ok());
+  assert!(check_delimiters("f(\")\", ')', '\\'', r#\"(\"#) // )\n/* ( /* ) */ */").is_ok());
+  assert!(check_delimiters("fn f<'a>(x: &'a str) {}").is_ok());
+  assert_eq!(
+    check_delimiters("(1"),
+    Err("unclosed delimiter `(`".to_string())
+  );
+  assert_eq!(
+    check_delimiters("(1]"),
+    Err("mismatched closing delimiter `]` for `(`".to_string())
+  );
+  assert_eq!(
+    check_delimiters("1)"),
+    Err("unexpected closing delimiter `)`".to_string())
+  );
+  assert!(check_delimiters("\"(").is_err());
+}
+
+#[cfg(feature = "syn")]
+#[test]
+fn check_check_snippets() {
+  let rng = types::Range::dummy();
+  let setting = (
+    (rng, "lexer::Token".to_string()),
+    vec![(
+      rng,
+      "Tok_A".to_string(),
+      (
+        rng,
+        "(lexer::TokenKind::A, _) | (lexer::TokenKind::B, _)".to_string(),
+      ),
+    )],
+  );
+  let bnf = |type_str: &str, code: &str| {
+    types::Bnf::Pub(
+      rng,
+      "main".to_string(),
+      (rng, type_str.to_string()),
+      vec![(Vec::new(), (rng, code.to_string()))],
+    )
+  };
+  assert!(check_snippets(&setting, &[bnf("Vec<usize>", "let v = Vec::new();\n    v")]).is_ok());
+  assert!(check_snippets(&setting, &[bnf("()", "")]).is_ok());
+  assert!(matches!(
+    check_snippets(&setting, &[bnf("Vec<usize", "Vec::new()")]),
+    Err(error::Error::ConfigError(
+      error::ConfigError::InvalidTypeStr(_, _)
+    ))
+  ));
+  assert!(matches!(
+    check_snippets(&setting, &[bnf("()", "let v = ;")]),
+    Err(error::Error::ConfigError(error::ConfigError::InvalidCode(
+      _,
+      _
+    )))
+  ));
+}
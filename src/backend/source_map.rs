@@ -0,0 +1,109 @@
+use super::error;
+use super::types;
+
+// 生成されたコードに埋め込む、文法ファイル上の位置を表すコメント
+pub fn make_location_comment(
+  input_file_name: &str,
+  input_bytes: &[u8],
+  rng: &types::Range,
+) -> String {
+  let (_, (line, column), _) = error::get_error_point(*rng, input_bytes);
+  format!("// {}:{}:{}", input_file_name, line, column)
+}
+
+// 生成された関数とアクションのそれぞれについて、
+// 文法ファイル上の位置と生成されたコード上の行番号をJSONにする
+pub fn make_source_map_str(
+  term: &types::Term,
+  input_file_name: &str,
+  input: &str,
+  output_str: &str,
+) -> String {
+  let (_, _, bnfs) = term;
+  let input_bytes = input.as_bytes();
+  let mut rules_str_lst = Vec::new();
+  for bnf in bnfs.iter() {
+    let (rng, name, code_lst) = match bnf {
+      types::Bnf::Pub(rng, name, _, code_lst) => (rng, name, code_lst),
+      types::Bnf::NonPub(rng, name, _, code_lst) => (rng, name, code_lst),
+    };
+    let mut alternatives_str_lst = Vec::new();
+    for (i, (_, (code_rng, _))) in code_lst.iter().enumerate() {
+      alternatives_str_lst.push(format!(
+        "        {{ \"index\": {}, {} }}",
+        i,
+        make_position_str(input_file_name, input_bytes, code_rng, output_str)
+      ))
+    }
+    rules_str_lst.push(format!(
+      "    {{
+      \"name\": {},
+      {},
+      \"alternatives\": [
+{}
+      ]
+    }}",
+      escape_json_str(name),
+      make_position_str(input_file_name, input_bytes, rng, output_str),
+      alternatives_str_lst.join(",\n")
+    ))
+  }
+  format!(
+    "{{
+  \"file\": {},
+  \"rules\": [
+{}
+  ]
+}}
+",
+    escape_json_str(input_file_name),
+    rules_str_lst.join(",\n")
+  )
+}
+
+fn make_position_str(
+  input_file_name: &str,
+  input_bytes: &[u8],
+  rng: &types::Range,
+  output_str: &str,
+) -> String {
+  let (_, (line, column), _) = error::get_error_point(*rng, input_bytes);
+  let comment = make_location_comment(input_file_name, input_bytes, rng);
+  // 位置情報のコメントは文法ファイル上の位置ごとに一意なので、それを探せば良い
+  let generated_line_str = match output_str
+    .lines()
+    .position(|line_str| line_str.trim() == comment)
+  {
+    Some(n) => (n + 1).to_string(),
+    None => "null".to_string(),
+  };
+  format!(
+    "\"line\": {}, \"column\": {}, \"generated_line\": {}",
+    line, column, generated_line_str
+  )
+}
+
+pub fn escape_json_str(s: &str) -> String {
+  let mut escaped = String::from("\"");
+  for c in s.chars() {
+    match c {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+      c => escaped.push(c),
+    }
+  }
+  escaped.push('"');
+  escaped
+}
+
+#[test]
+fn check_escape_json_str() {
+  assert_eq!(
+    escape_json_str("a\"b\\c\nd"),
+    "\"a\\\"b\\\\c\\nd\"".to_string()
+  );
+}
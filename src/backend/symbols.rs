@@ -0,0 +1,197 @@
+use super::super::frontend::lexer;
+use super::error;
+use super::types;
+
+// 文法ファイルの中で規則とトークンの名前が現れる位置を集める
+// エディタでの定義への移動や参照の検索、名前の変更に使う
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+  Rule,
+  Token,
+}
+
+/// 規則やトークンの名前が現れる1か所
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+  pub kind: SymbolKind,
+  pub name: String,
+  /// 名前の部分だけの位置情報
+  pub range: types::Range,
+  /// 規則の定義や`extern`の中のトークンの宣言ならtrue
+  pub is_definition: bool,
+}
+
+// 定義の位置情報は定義全体を指し、名前から始まる
+fn get_name_range(rng: &types::Range, name: &str) -> types::Range {
+  let (start, _) = rng.to_tuple();
+  types::Range::make(start, name.len())
+}
+
+/// 規則とトークンの名前が現れる全ての位置を、ファイルの中での順に返す
+pub fn make_occurrence_lst(term: &types::Term) -> Vec<Occurrence> {
+  let (_, (_, token_lst), bnfs) = term;
+  let mut occurrence_lst = Vec::new();
+  for (rng, name, _) in token_lst.iter() {
+    occurrence_lst.push(Occurrence {
+      kind: SymbolKind::Token,
+      name: name.clone(),
+      range: get_name_range(rng, name),
+      is_definition: true,
+    })
+  }
+  for bnf in bnfs.iter() {
+    let (rng, name, code_lst) = match bnf {
+      types::Bnf::Pub(rng, name, _, code_lst) => (rng, name, code_lst),
+      types::Bnf::NonPub(rng, name, _, code_lst) => (rng, name, code_lst),
+    };
+    occurrence_lst.push(Occurrence {
+      kind: SymbolKind::Rule,
+      name: name.clone(),
+      range: get_name_range(rng, name),
+      is_definition: true,
+    });
+    for (fn_or_token_lst, _) in code_lst.iter() {
+      for (_, fn_or_token) in fn_or_token_lst.iter() {
+        let kind = match fn_or_token {
+          types::FnOrToken::Function(_, _) => SymbolKind::Rule,
+          types::FnOrToken::Token(_, _) => SymbolKind::Token,
+        };
+        occurrence_lst.push(Occurrence {
+          kind,
+          name: fn_or_token.name().clone(),
+          range: fn_or_token.range(),
+          is_definition: false,
+        })
+      }
+    }
+  }
+  occurrence_lst
+}
+
+/// `pos`（バイト単位）にある名前を返す
+///
+/// 名前の直後の位置も、その名前の上にあるものとして扱う。
+pub fn find_occurrence_at(occurrence_lst: &[Occurrence], pos: usize) -> Option<&Occurrence> {
+  occurrence_lst.iter().find(|occurrence| {
+    let (start, end) = occurrence.range.to_tuple();
+    start <= pos && pos <= end
+  })
+}
+
+/// 同じ名前の規則またはトークンが現れる全ての位置を返す
+pub fn find_same_symbol<'a>(
+  occurrence_lst: &'a [Occurrence],
+  kind: SymbolKind,
+  name: &str,
+) -> Vec<&'a Occurrence> {
+  occurrence_lst
+    .iter()
+    .filter(|occurrence| occurrence.kind == kind && occurrence.name == name)
+    .collect()
+}
+
+// 新しい名前が、字句解析で同じ種類の名前1つになるか確認する
+fn is_valid_name(kind: SymbolKind, name: &str) -> bool {
+  match lexer::lex(name).as_deref() {
+    Ok([(lexer::TokenKind::VAR(s), _), (lexer::TokenKind::EOF, _)]) => {
+      kind == SymbolKind::Rule && s == name
+    }
+    Ok([(lexer::TokenKind::CONSTRUCTOR(s), _), (lexer::TokenKind::EOF, _)]) => {
+      kind == SymbolKind::Token && s == name
+    }
+    _ => false,
+  }
+}
+
+/// 規則またはトークンの名前を変えるときに書き換える位置を返す
+///
+/// 名前が見つからないときや、新しい名前が使えないときはエラーを返す。
+pub fn make_rename_range_lst(
+  term: &types::Term,
+  old_name: &str,
+  new_name: &str,
+) -> Result<Vec<types::Range>, error::Error> {
+  let occurrence_lst = make_occurrence_lst(term);
+  let kind = match occurrence_lst
+    .iter()
+    .find(|occurrence| occurrence.is_definition && occurrence.name == old_name)
+  {
+    Some(occurrence) => occurrence.kind,
+    None => {
+      return Err(error::Error::RenameError(error::RenameError::NotFoundName(
+        old_name.to_string(),
+      )))
+    }
+  };
+  if !is_valid_name(kind, new_name) {
+    let name = new_name.to_string();
+    return Err(error::Error::RenameError(match kind {
+      SymbolKind::Rule => error::RenameError::InvalidRuleName(name),
+      SymbolKind::Token => error::RenameError::InvalidTokenName(name),
+    }));
+  }
+  if old_name != new_name && !find_same_symbol(&occurrence_lst, kind, new_name).is_empty() {
+    return Err(error::Error::RenameError(
+      error::RenameError::AlreadyDefinedName(new_name.to_string()),
+    ));
+  }
+  Ok(
+    find_same_symbol(&occurrence_lst, kind, old_name)
+      .into_iter()
+      .map(|occurrence| occurrence.range)
+      .collect(),
+  )
+}
+
+/// 規則またはトークンの名前を変えた文法ファイルの中身と、書き換えた箇所の数を返す
+///
+/// 名前が現れる位置だけを書き換えるので、アクションのコードやコメントはそのまま残る。
+pub fn rename(
+  term: &types::Term,
+  input: &str,
+  old_name: &str,
+  new_name: &str,
+) -> Result<(String, usize), error::Error> {
+  let mut range_lst = make_rename_range_lst(term, old_name, new_name)?;
+  range_lst.sort_by_key(|rng| rng.to_tuple());
+  let mut output = String::new();
+  let mut pos = 0;
+  for rng in range_lst.iter() {
+    let (start, end) = rng.to_tuple();
+    output.push_str(&input[pos..start]);
+    output.push_str(new_name);
+    pos = end;
+  }
+  output.push_str(&input[pos..]);
+  Ok((output, range_lst.len()))
+}
+
+#[test]
+fn check_rename() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+// sub
+pub main: \"()\" = {
+  <sub: sub> => {\"sub\"},
+};
+sub: \"()\" = {
+  <_a: Tok_A> <_s: sub> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let (output, count) = rename(&term, input, "sub", "item").unwrap();
+  assert_eq!(count, 3);
+  assert!(output.contains("// sub\npub main"));
+  assert!(output.contains("<sub: item> => {\"sub\"},"));
+  assert!(output.contains("item: \"()\" = {\n  <_a: Tok_A> <_s: item>"));
+  assert_eq!(rename(&term, input, "Tok_A", "Tok_B").unwrap().1, 2);
+  assert!(rename(&term, input, "sub", "Item").is_err());
+  assert!(rename(&term, input, "sub", "main").is_err());
+  assert!(rename(&term, input, "none", "item").is_err());
+}
@@ -0,0 +1,220 @@
+use super::analysis;
+use super::source_map::escape_json_str;
+use super::types;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// 生成されるパーサが使う解析の結果（nullable、FIRST、FOLLOW、predict表）を出力する
+// predict表は規則ごとに、トークンからそのトークンで選ばれる選択肢の番号のリストへの対応を表す
+// 番号が2つ以上あるトークンはLL(1)の衝突になっている
+
+struct RuleTable<'a> {
+  name: &'a String,
+  is_nullable: bool,
+  first_set: &'a BTreeSet<String>,
+  follow_set: &'a BTreeSet<String>,
+  predict_table: BTreeMap<&'a String, Vec<usize>>,
+  empty_alternative_lst: Vec<usize>,
+}
+
+fn make_rule_tables<'a>(
+  bnfs: &'a [types::Bnf],
+  nullable_set: &BTreeSet<String>,
+  first_set_map: &'a HashMap<String, BTreeSet<String>>,
+  follow_set_map: &'a HashMap<String, BTreeSet<String>>,
+  predict_set_map: &'a HashMap<String, Vec<BTreeSet<String>>>,
+) -> Vec<RuleTable<'a>> {
+  let mut rule_tables = Vec::new();
+  for bnf in bnfs.iter() {
+    let (name, code_lst) = match bnf {
+      types::Bnf::Pub(_, name, _, code_lst) => (name, code_lst),
+      types::Bnf::NonPub(_, name, _, code_lst) => (name, code_lst),
+    };
+    let mut predict_table: BTreeMap<&String, Vec<usize>> = BTreeMap::new();
+    for (i, predict_set) in predict_set_map[name].iter().enumerate() {
+      for tok_name in predict_set.iter() {
+        predict_table.entry(tok_name).or_default().push(i)
+      }
+    }
+    let empty_alternative_lst = code_lst
+      .iter()
+      .enumerate()
+      .filter(|(_, (fn_or_token_lst, _))| fn_or_token_lst.is_empty())
+      .map(|(i, _)| i)
+      .collect();
+    rule_tables.push(RuleTable {
+      name,
+      is_nullable: nullable_set.contains(name),
+      first_set: &first_set_map[name],
+      follow_set: &follow_set_map[name],
+      predict_table,
+      empty_alternative_lst,
+    })
+  }
+  rule_tables
+}
+
+fn with_rule_tables<F>(term: &types::Term, f: F) -> String
+where
+  F: FnOnce(&[RuleTable]) -> String,
+{
+  let (_, _, bnfs) = term;
+  let nullable_set = analysis::make_nullable_set(bnfs);
+  let first_set_map = analysis::make_first_set_map(bnfs);
+  let follow_set_map = analysis::make_follow_set_map(bnfs, &first_set_map, &nullable_set);
+  let predict_set_map = analysis::make_predict_set_map(bnfs);
+  f(&make_rule_tables(
+    bnfs,
+    &nullable_set,
+    &first_set_map,
+    &follow_set_map,
+    &predict_set_map,
+  ))
+}
+
+pub fn make_tables_str(term: &types::Term) -> String {
+  with_rule_tables(term, |rule_tables| {
+    let mut s = String::new();
+    for rule_table in rule_tables.iter() {
+      s.push_str(&format!("rule `{}`\n", rule_table.name));
+      s.push_str(&format!(
+        "  nullable: {}\n",
+        if rule_table.is_nullable { "yes" } else { "no" }
+      ));
+      s.push_str(&format!(
+        "  FIRST:    {}\n",
+        analysis::set_to_str(rule_table.first_set)
+      ));
+      s.push_str(&format!(
+        "  FOLLOW:   {}\n",
+        analysis::set_to_str(rule_table.follow_set)
+      ));
+      s.push_str("  predict:\n");
+      let width = rule_table
+        .predict_table
+        .keys()
+        .map(|tok_name| tok_name.len())
+        .max()
+        .unwrap_or(0);
+      for (tok_name, i_lst) in rule_table.predict_table.iter() {
+        let i_str_lst: Vec<String> = i_lst
+          .iter()
+          .map(|i| {
+            if rule_table.empty_alternative_lst.contains(i) {
+              format!("{} (empty)", i)
+            } else {
+              i.to_string()
+            }
+          })
+          .collect();
+        let conflict_str = if i_lst.len() > 1 { "  [conflict]" } else { "" };
+        s.push_str(&format!(
+          "    {:width$} -> {}{}\n",
+          tok_name,
+          i_str_lst.join(", "),
+          conflict_str,
+          width = width
+        ));
+      }
+      s.push('\n');
+    }
+    s.push_str(&format!(
+      "(`{}` is the end of input)\n",
+      analysis::END_OF_INPUT
+    ));
+    s
+  })
+}
+
+fn set_to_json_str(set: &BTreeSet<String>) -> String {
+  let names: Vec<String> = set.iter().map(|s| escape_json_str(s)).collect();
+  format!("[{}]", names.join(", "))
+}
+
+pub fn make_tables_json_str(term: &types::Term) -> String {
+  with_rule_tables(term, |rule_tables| {
+    let mut rules_str_lst = Vec::new();
+    for rule_table in rule_tables.iter() {
+      let predict_str_lst: Vec<String> = rule_table
+        .predict_table
+        .iter()
+        .map(|(tok_name, i_lst)| {
+          let i_str_lst: Vec<String> = i_lst.iter().map(|i| i.to_string()).collect();
+          format!(
+            "        {}: [{}]",
+            escape_json_str(tok_name),
+            i_str_lst.join(", ")
+          )
+        })
+        .collect();
+      let empty_str_lst: Vec<String> = rule_table
+        .empty_alternative_lst
+        .iter()
+        .map(|i| i.to_string())
+        .collect();
+      rules_str_lst.push(format!(
+        "    {{
+      \"name\": {},
+      \"nullable\": {},
+      \"first\": {},
+      \"follow\": {},
+      \"empty_alternatives\": [{}],
+      \"predict\": {{
+{}
+      }}
+    }}",
+        escape_json_str(rule_table.name),
+        rule_table.is_nullable,
+        set_to_json_str(rule_table.first_set),
+        set_to_json_str(rule_table.follow_set),
+        empty_str_lst.join(", "),
+        predict_str_lst.join(",\n")
+      ))
+    }
+    format!(
+      "{{
+  \"end_of_input\": {},
+  \"rules\": [
+{}
+  ]
+}}
+",
+      escape_json_str(analysis::END_OF_INPUT),
+      rules_str_lst.join(",\n")
+    )
+  })
+}
+
+#[test]
+fn check_make_tables_str() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+    Tok_B => \"'b'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: opt> <_b: Tok_B> => {\"()\"},
+};
+opt: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let tables_str = make_tables_str(&term);
+  assert!(tables_str.contains(
+    "rule `opt`
+  nullable: yes
+  FIRST:    {Tok_A}
+  FOLLOW:   {Tok_B}
+  predict:
+    Tok_A -> 0
+    Tok_B -> 1 (empty)
+"
+  ));
+  assert!(tables_str.contains("    Tok_A -> 0\n    Tok_B -> 0\n"));
+  let tables_json_str = make_tables_json_str(&term);
+  assert!(tables_json_str.contains("\"follow\": [\"$\"]"));
+  assert!(tables_json_str.contains("\"Tok_B\": [1]"));
+}
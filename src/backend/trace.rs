@@ -0,0 +1,124 @@
+// 生成されたパーサで、規則とトークンを読み始めたとき・読み終えたとき・選択肢を選んだときに、
+// 利用者が実装したParseTracerのメソッドを呼ぶ
+// トレースを使うときは、各関数の引数の最後に`tracer: &mut dyn ParseTracer`を加え、
+// 規則の関数は本体を`_parse_body_*`に移して、前後でイベントを送る`_parse_fn_*`で包む
+
+/// 関数の引数に加えるtracer
+pub const TRACER_PARAM: &str = "tracer: &mut dyn ParseTracer";
+
+/// 選択肢を選んだときにイベントを送るコード
+pub fn make_choose_code(rule_name: &str, index: usize) -> String {
+  format!(
+    "tracer.choose_alternative({:?}, pos, {});",
+    rule_name, index
+  )
+}
+
+/// 規則を読む関数の本体`_parse_body_*`を呼び、その前後でイベントを送る`_parse_fn_*`
+pub fn make_wrapper_fn_str(rule_name: &str, main_type_str: &str, type_str: &str) -> String {
+  format!(
+    "
+#[allow(non_snake_case)]
+#[allow(clippy::type_complexity)]
+fn _parse_fn_{name}(
+  tokens: &[{main_type}],
+  pos: usize,
+  {param},
+) -> Result<({ty}, usize), ParseError>
+{{
+  tracer.enter_rule({name:?}, pos);
+  let result = _parse_body_{name}(tokens, pos, tracer);
+  tracer.exit_rule({name:?}, pos, result.as_ref().ok().map(|(_, end)| *end));
+  result
+}}
+",
+    name = rule_name,
+    main_type = main_type_str,
+    param = TRACER_PARAM,
+    ty = type_str
+  )
+}
+
+/// ParseTracerトレイトと、標準エラー出力に書き出す実装
+pub fn make_tracer_str() -> String {
+  "
+/// Receives the events of a parser generated with `--trace`.
+///
+/// Every method does nothing by default, so implement only the ones you need,
+/// e.g. to forward the events to `log` or `tracing`.
+#[allow(unused_variables)]
+pub trait ParseTracer {
+  /// Called when the parser starts reading `rule` at the token `pos`.
+  fn enter_rule(&mut self, rule: &str, pos: usize) {}
+  /// Called when the alternative `alternative` (counted from 0 in the grammar) of `rule` is chosen.
+  fn choose_alternative(&mut self, rule: &str, pos: usize, alternative: usize) {}
+  /// Called when the parser finishes reading `rule` started at `pos`,
+  /// with the position after it, or `None` if the rule was rejected.
+  fn exit_rule(&mut self, rule: &str, pos: usize, end_opt: Option<usize>) {}
+  /// Called when the parser starts reading the token `token` at `pos`.
+  fn enter_token(&mut self, token: &str, pos: usize) {}
+  /// Called when the parser finishes reading the token `token` at `pos`.
+  fn exit_token(&mut self, token: &str, pos: usize, is_ok: bool) {}
+}
+
+/// Prints the events to stderr, indented by the depth of the rules.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub struct StderrTracer {
+  depth: usize,
+}
+
+impl ParseTracer for StderrTracer {
+  fn enter_rule(&mut self, rule: &str, pos: usize) {
+    eprintln!(\"{}enter {} at {}\", \"  \".repeat(self.depth), rule, pos);
+    self.depth += 1;
+  }
+  fn choose_alternative(&mut self, rule: &str, pos: usize, alternative: usize) {
+    eprintln!(\"{}choose {} #{} at {}\", \"  \".repeat(self.depth), rule, alternative, pos);
+  }
+  fn exit_rule(&mut self, rule: &str, pos: usize, end_opt: Option<usize>) {
+    self.depth = self.depth.saturating_sub(1);
+    match end_opt {
+      Some(end) => eprintln!(\"{}exit {} at {}..{}\", \"  \".repeat(self.depth), rule, pos, end),
+      None => eprintln!(\"{}fail {} at {}\", \"  \".repeat(self.depth), rule, pos),
+    }
+  }
+  fn exit_token(&mut self, token: &str, pos: usize, is_ok: bool) {
+    let result = if is_ok { \"ok\" } else { \"failed\" };
+    eprintln!(\"{}token {} at {}: {}\", \"  \".repeat(self.depth), token, pos, result);
+  }
+}
+
+// A ParseTracer which does nothing, used by parse().
+struct _NoTracer;
+
+impl ParseTracer for _NoTracer {}
+"
+  .to_string()
+}
+
+#[test]
+fn check_trace_code() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_a: Tok_A> <_m: main> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let options = super::GenerateOptions {
+    is_trace: true,
+    ..super::GenerateOptions::default()
+  };
+  let output = super::to_string_with_options(term, "a.mkr", input, &options).unwrap();
+  assert!(output.contains("pub fn parse_with_tracer("));
+  assert!(output.contains("let result = _parse_body_main(tokens, pos, tracer);"));
+  assert!(output.contains("tracer.choose_alternative(\"main\", pos, 1);"));
+  assert!(output.contains("_parse_token_Tok_A(tokens, pos, tracer)?"));
+  assert!(output.contains("tracer.exit_token(\"Tok_A\", pos, result.is_ok());"));
+}
@@ -0,0 +1,335 @@
+use super::ast_type::{AstType, Field, FieldKind};
+use super::cst;
+use super::types;
+
+// 生成した構文木の型を辿るVisitorトレイトと、作り直すFoldトレイトを生成する
+// 型を省略した規則から生成した型では型ごとに、具象構文木では`Node`とトークンについてメソッドを作る
+// メソッドの既定の実装は子を順に辿る関数（`walk_*`と`fold_*_children`）を呼ぶので、
+// 利用者は必要なメソッドだけを実装すればよい
+// 型を書いた規則の値は中身が分からないので辿らない
+
+// フィールドを辿るコード（`value`はフィールドへの参照）
+fn make_visit_field_str(field: &Field, value: &str) -> Option<String> {
+  match &field.kind {
+    FieldKind::Token => Some(format!("visitor.visit_token({});", value)),
+    FieldKind::Rule(_) => None,
+    FieldKind::AutoRule(rule_name) => Some(format!("visitor.visit_{}({});", rule_name, value)),
+  }
+}
+
+// フィールドを作り直すコード（`value`はフィールドの値）
+fn make_fold_field_str(field: &Field, value: &str) -> String {
+  match &field.kind {
+    FieldKind::Token => format!("folder.fold_token({})", value),
+    FieldKind::Rule(_) => value.to_string(),
+    FieldKind::AutoRule(rule_name) if field.is_boxed => {
+      format!("Box::new(folder.fold_{}(*{}))", rule_name, value)
+    }
+    FieldKind::AutoRule(rule_name) => format!("folder.fold_{}({})", rule_name, value),
+  }
+}
+
+// `Name { a: folder.fold_token(a), b }`の形の値を作るコード
+fn make_fold_construct_str(path: &str, field_lst: &[Field]) -> String {
+  let init_lst: Vec<String> = field_lst
+    .iter()
+    .map(|field| {
+      let fold_str = make_fold_field_str(field, &field.name);
+      if fold_str == field.name {
+        fold_str
+      } else {
+        format!("{}: {}", field.name, fold_str)
+      }
+    })
+    .collect();
+  if init_lst.is_empty() {
+    format!("{} {{}}", path)
+  } else {
+    format!("{} {{ {} }}", path, init_lst.join(", "))
+  }
+}
+
+// `Name { a, b }`の形のパターン
+fn make_pattern_str(path: &str, field_lst: &[Field]) -> String {
+  let name_lst: Vec<&str> = field_lst.iter().map(|field| field.name.as_str()).collect();
+  if name_lst.is_empty() {
+    format!("{} {{}}", path)
+  } else {
+    format!("{} {{ {} }}", path, name_lst.join(", "))
+  }
+}
+
+fn make_walk_fn_str(ast_type: &AstType) -> String {
+  let mut body_str = String::new();
+  match ast_type {
+    AstType::Struct(_, _, field_lst) => {
+      for field in field_lst.iter() {
+        if let Some(s) = make_visit_field_str(field, &format!("&node.{}", field.name)) {
+          body_str.push_str(&format!("  {}\n", s))
+        }
+      }
+    }
+    AstType::Enum(type_name, _, variant_lst) => {
+      body_str.push_str("  match node {\n");
+      for (label, field_lst) in variant_lst.iter() {
+        let visit_str_lst: Vec<(&String, String)> = field_lst
+          .iter()
+          .filter_map(|field| make_visit_field_str(field, &field.name).map(|s| (&field.name, s)))
+          .collect();
+        if field_lst.is_empty() {
+          body_str.push_str(&format!("    {}::{} => {{}}\n", type_name, label))
+        } else if visit_str_lst.is_empty() {
+          body_str.push_str(&format!("    {}::{} {{ .. }} => {{}}\n", type_name, label))
+        } else {
+          let name_lst: Vec<&str> = visit_str_lst
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+          body_str.push_str(&format!(
+            "    {}::{} {{ {}, .. }} => {{\n",
+            type_name,
+            label,
+            name_lst.join(", ")
+          ));
+          for (_, s) in visit_str_lst.iter() {
+            body_str.push_str(&format!("      {}\n", s))
+          }
+          body_str.push_str("    }\n")
+        }
+      }
+      body_str.push_str("  }\n")
+    }
+  }
+  format!(
+    "
+/// Visits the fields of `{type_name}` in order.
+#[allow(dead_code)]
+#[allow(unused_variables)]
+pub fn walk_{rule_name}<V: Visitor + ?Sized>(visitor: &mut V, node: &{type_name}) {{
+{body}}}
+",
+    type_name = ast_type.type_name(),
+    rule_name = ast_type.rule_name(),
+    body = body_str
+  )
+}
+
+fn make_fold_children_fn_str(ast_type: &AstType) -> String {
+  let body_str = match ast_type {
+    AstType::Struct(type_name, _, field_lst) => format!(
+      "  let {} = node;\n  {}\n",
+      make_pattern_str(type_name, field_lst),
+      make_fold_construct_str(type_name, field_lst)
+    ),
+    AstType::Enum(type_name, _, variant_lst) => {
+      let mut s = String::from("  match node {\n");
+      for (label, field_lst) in variant_lst.iter() {
+        let path = format!("{}::{}", type_name, label);
+        if field_lst.is_empty() {
+          s.push_str(&format!("    {} => {},\n", path, path))
+        } else {
+          s.push_str(&format!(
+            "    {} => {},\n",
+            make_pattern_str(&path, field_lst),
+            make_fold_construct_str(&path, field_lst)
+          ))
+        }
+      }
+      s.push_str("  }\n");
+      s
+    }
+  };
+  format!(
+    "
+/// Rebuilds `{type_name}` from its folded fields.
+#[allow(dead_code)]
+#[allow(unused_variables)]
+pub fn fold_{rule_name}_children<F: Fold + ?Sized>(folder: &mut F, node: {type_name}) -> {type_name} {{
+{body}}}
+",
+    type_name = ast_type.type_name(),
+    rule_name = ast_type.rule_name(),
+    body = body_str
+  )
+}
+
+/// 型を省略した規則から生成した型を辿る`Visitor`と、作り直す`Fold`
+pub fn make_ast_visitor_str(ast_type_lst: &[AstType], main_type_str: &str) -> String {
+  let mut visit_method_str = String::new();
+  let mut fold_method_str = String::new();
+  let mut fn_str = String::new();
+  for ast_type in ast_type_lst.iter() {
+    visit_method_str.push_str(&format!(
+      "  fn visit_{rule_name}(&mut self, node: &{type_name}) {{
+    walk_{rule_name}(self, node)
+  }}
+",
+      rule_name = ast_type.rule_name(),
+      type_name = ast_type.type_name()
+    ));
+    fold_method_str.push_str(&format!(
+      "  fn fold_{rule_name}(&mut self, node: {type_name}) -> {type_name} {{
+    fold_{rule_name}_children(self, node)
+  }}
+",
+      rule_name = ast_type.rule_name(),
+      type_name = ast_type.type_name()
+    ));
+    fn_str.push_str(&make_walk_fn_str(ast_type));
+    fn_str.push_str(&make_fold_children_fn_str(ast_type));
+  }
+  format!(
+    "
+/// Visits the syntax trees generated for the rules without a type.
+///
+/// Each `visit_*` method calls the `walk_*` function of the same type by default,
+/// which visits the fields in order, so implement only the methods you need
+/// and call `walk_*` from them to visit the fields too.
+/// Values of the rules with a type are not visited.
+#[allow(dead_code)]
+#[allow(unused_variables)]
+pub trait Visitor {{
+{visit_methods}  fn visit_token(&mut self, token: &{main_type}) {{}}
+}}
+
+/// Rebuilds the syntax trees generated for the rules without a type.
+///
+/// Each `fold_*` method calls the `fold_*_children` function of the same type by default,
+/// which rebuilds the value from its folded fields, so implement only the methods you need.
+/// Values of the rules with a type are kept as they are.
+#[allow(dead_code)]
+pub trait Fold {{
+{fold_methods}  fn fold_token(&mut self, token: {main_type}) -> {main_type} {{
+    token
+  }}
+}}
+{fns}",
+    visit_methods = visit_method_str,
+    fold_methods = fold_method_str,
+    main_type = main_type_str,
+    fns = fn_str
+  )
+}
+
+/// 具象構文木の`Node`を辿る`Visitor`と、作り直す`Fold`
+pub fn make_cst_visitor_str(bnfs: &[types::Bnf], main_type_str: &str) -> String {
+  let mut fold_arm_str = String::new();
+  for bnf in bnfs.iter() {
+    let rule_name = match bnf {
+      types::Bnf::Pub(_, name, _, _) | types::Bnf::NonPub(_, name, _, _) => name,
+    };
+    fold_arm_str.push_str(&format!(
+      "    Node::{name}(alternative, children) => Node::{name}(alternative, _fold_child_lst(folder, children)),\n",
+      name = cst::to_type_name(rule_name)
+    ))
+  }
+  format!(
+    "
+/// Visits the concrete syntax tree.
+///
+/// `visit_node` calls `walk_node` by default, which visits the children in source order,
+/// so implement only the methods you need and call `walk_node` from `visit_node`
+/// to visit the children too.
+#[allow(dead_code)]
+#[allow(unused_variables)]
+pub trait Visitor {{
+  fn visit_node(&mut self, node: &Node) {{
+    walk_node(self, node)
+  }}
+  fn visit_token(&mut self, token: &{main_type}) {{}}
+}}
+
+/// Visits the children of `node` in source order.
+#[allow(dead_code)]
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {{
+  for child in node.children().iter() {{
+    match child {{
+      Child::Token(token) => visitor.visit_token(token),
+      Child::Node(node) => visitor.visit_node(node),
+    }}
+  }}
+}}
+
+/// Rebuilds the concrete syntax tree.
+///
+/// `fold_node` calls `fold_node_children` by default, which rebuilds the node
+/// from its folded children, so implement only the methods you need.
+#[allow(dead_code)]
+pub trait Fold {{
+  fn fold_node(&mut self, node: Node) -> Node {{
+    fold_node_children(self, node)
+  }}
+  fn fold_token(&mut self, token: {main_type}) -> {main_type} {{
+    token
+  }}
+}}
+
+/// Rebuilds `node` from its folded children, keeping its rule and alternative.
+#[allow(dead_code)]
+pub fn fold_node_children<F: Fold + ?Sized>(folder: &mut F, node: Node) -> Node {{
+  match node {{
+{fold_arms}  }}
+}}
+
+#[allow(dead_code)]
+fn _fold_child_lst<F: Fold + ?Sized>(folder: &mut F, children: Vec<Child>) -> Vec<Child> {{
+  children
+    .into_iter()
+    .map(|child| match child {{
+      Child::Token(token) => Child::Token(folder.fold_token(token)),
+      Child::Node(node) => Child::Node(folder.fold_node(node)),
+    }})
+    .collect()
+}}
+",
+    main_type = main_type_str,
+    fold_arms = fold_arm_str
+  )
+}
+
+#[test]
+fn check_visitor() {
+  let input = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_NUM => \"'0'\",
+    Tok_PLUS => \"'+'\",
+  }
+}
+pub expr = {
+  <lhs: Tok_NUM> <tail: expr_tail>
+};
+expr_tail = {
+  <_p: Tok_PLUS> <rhs: expr> => Add,
+  => Empty,
+};
+";
+  let term = super::super::frontend::get_ast(input).unwrap();
+  let ast_type_lst = super::ast_type::make_ast_type_lst(&term);
+  let output = make_ast_visitor_str(&ast_type_lst, "Token");
+  assert!(output.contains(
+    "  fn visit_expr_tail(&mut self, node: &ExprTail) {\n    walk_expr_tail(self, node)\n  }"
+  ));
+  assert!(
+    output.contains("  visitor.visit_token(&node.lhs);\n  visitor.visit_expr_tail(&node.tail);\n")
+  );
+  assert!(
+    output.contains("    ExprTail::Add { rhs, .. } => {\n      visitor.visit_expr(rhs);\n    }\n")
+  );
+  assert!(output.contains(
+    "  let Expr { lhs, tail } = node;\n  Expr { lhs: folder.fold_token(lhs), tail: folder.fold_expr_tail(tail) }\n"
+  ));
+  assert!(output.contains(
+    "    ExprTail::Add { rhs } => ExprTail::Add { rhs: Box::new(folder.fold_expr(*rhs)) },\n"
+  ));
+  let (_, _, bnfs) = &term;
+  let output = make_cst_visitor_str(bnfs, "Token");
+  assert!(output.contains(
+    "    Node::ExprTail(alternative, children) => Node::ExprTail(alternative, _fold_child_lst(folder, children)),\n"
+  ));
+  let (_, _, bnfs) = super::super::frontend::get_ast(&input.replace("expr_tail", "token")).unwrap();
+  assert!(super::ast_type::check_auto_rules(&bnfs).is_err());
+  let (_, _, bnfs) =
+    super::super::frontend::get_ast(&input.replace("expr_tail", "visitor")).unwrap();
+  assert!(super::ast_type::check_auto_rules(&bnfs).is_err());
+}
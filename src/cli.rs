@@ -0,0 +1,184 @@
+// コマンドラインの各サブコマンドと、それらが共通して使う入出力の処理
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use llmaker::{backend, error, frontend, types};
+
+pub mod batch;
+pub mod check;
+pub mod coverage;
+pub mod fmt;
+pub mod generate;
+pub mod rename;
+pub mod repl;
+pub mod sample;
+pub mod test;
+pub mod watch;
+
+// 標準入力・標準出力を表すファイル名
+pub const STDIO_FILE_NAME: &str = "-";
+const STDIN_DISPLAY_NAME: &str = "<stdin>";
+
+// 経過のメッセージをどこまで表示するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+  Quiet,
+  Normal,
+  Verbose,
+}
+
+// 生成したコードを標準出力に書き出すときは、経過のメッセージを出さない
+pub fn print_line(is_quiet: bool) {
+  if !is_quiet {
+    println!(" --- --- ---");
+  }
+}
+
+pub fn print_msg(is_quiet: bool, s: &str) {
+  if !is_quiet {
+    println!("  {}", s);
+  }
+}
+
+// --trace-analysisが指定されていれば、各規則の選択肢の選ばれ方を標準エラー出力に表示する
+pub fn print_trace_analysis(is_trace_analysis: bool, ast: &types::Term) {
+  if is_trace_analysis {
+    let (_, _, bnfs) = ast;
+    eprint!("{}", backend::analysis::make_trace_str(bnfs));
+  }
+}
+
+pub fn write_file(file_name: String, text: String) {
+  if file_name == STDIO_FILE_NAME {
+    let stdout = std::io::stdout();
+    let mut handle = stdout.lock();
+    handle.write_all(text.as_bytes()).unwrap();
+  } else {
+    let mut file = File::create(file_name).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+  }
+}
+
+pub fn read_input_file(input_file_name: &str) -> Result<String, error::Error> {
+  let mut f = match File::open(input_file_name) {
+    Ok(v) => Ok(v),
+    Err(_) => Err(error::Error::OptionError(
+      error::OptionError::NotFoundInputFileName(input_file_name.to_string()),
+    )),
+  }?;
+  let mut contents = String::new();
+  match f.read_to_string(&mut contents) {
+    Ok(_) => Ok(()),
+    Err(_) => Err(error::Error::OptionError(
+      error::OptionError::BrokenInputFile(input_file_name.to_string()),
+    )),
+  }?;
+  Ok(contents)
+}
+
+fn read_input(input_file_name: &str) -> Result<String, error::Error> {
+  if input_file_name == STDIO_FILE_NAME {
+    let mut contents = String::new();
+    match std::io::stdin().read_to_string(&mut contents) {
+      Ok(_) => Ok(contents),
+      Err(_) => Err(error::Error::OptionError(
+        error::OptionError::BrokenInputFile(STDIN_DISPLAY_NAME.to_string()),
+      )),
+    }
+  } else {
+    read_input_file(input_file_name)
+  }
+}
+
+pub fn get_display_name(input_file_name: &str) -> &str {
+  if input_file_name == STDIO_FILE_NAME {
+    STDIN_DISPLAY_NAME
+  } else {
+    input_file_name
+  }
+}
+
+// test、repl、generate-samplesで使う文法を読み込み、生成するときと同じ検査をする
+// 左再帰があると文法をそのまま解釈できないので、左再帰はエラーにする
+pub fn load_grammar(display_name: &str, contents: &str) -> Result<types::Term, error::Error> {
+  let ast = frontend::get_ast(contents)?;
+  let _ = backend::to_string(ast.clone(), display_name, contents)?;
+  let (_, _, bnfs) = &ast;
+  backend::analysis::check_left_recursion(bnfs)?;
+  Ok(ast)
+}
+
+// 最初のpubな規則（無ければ最初の規則）
+pub fn get_start_rule_name(ast: &types::Term) -> Option<String> {
+  let (_, _, bnfs) = ast;
+  bnfs
+    .iter()
+    .find_map(|bnf| match bnf {
+      types::Bnf::Pub(_, name, _, _) => Some(name.clone()),
+      types::Bnf::NonPub(_, _, _, _) => None,
+    })
+    .or_else(|| {
+      bnfs.first().map(|bnf| match bnf {
+        types::Bnf::Pub(_, name, _, _) | types::Bnf::NonPub(_, name, _, _) => name.clone(),
+      })
+    })
+}
+
+// 数を指定するオプションの値を読む
+// 0を許さないときはis_positiveをtrueにする
+pub fn get_number_option(
+  matches: &clap::ArgMatches,
+  name: &str,
+  default: u64,
+  is_positive: bool,
+) -> u64 {
+  match matches.value_of(name) {
+    None => default,
+    Some(s) => match s.parse::<u64>() {
+      Ok(n) if n > 0 || !is_positive => n,
+      _ => error::print_error_msg(
+        error::Error::OptionError(error::OptionError::InvalidNumber(
+          format!("--{}", name),
+          s.to_string(),
+        )),
+        None,
+        None,
+      ),
+    },
+  }
+}
+
+// 入力を読み込んでfに渡し、エラーがあれば表示して終了する
+pub fn run<F>(input_file_name_opt: Option<&str>, f: F)
+where
+  F: FnOnce(&str, &str) -> Result<(), error::Error>,
+{
+  let input_file_name = match input_file_name_opt {
+    Some(s) => s,
+    None => error::print_error_msg(
+      error::Error::OptionError(error::OptionError::NoInputFileName),
+      None,
+      None,
+    ),
+  };
+  let display_name = get_display_name(input_file_name);
+  let contents = match read_input(input_file_name) {
+    Ok(contents) => contents,
+    Err(e) => error::print_error_msg(e, Some(display_name), None),
+  };
+  match f(input_file_name, &contents) {
+    Ok(()) => (),
+    Err(e) => error::print_error_msg(e, Some(display_name), Some(&contents)),
+  }
+}
+
+pub fn get_verbosity(matches: &clap::ArgMatches) -> Verbosity {
+  if matches.is_present("quiet") {
+    Verbosity::Quiet
+  } else if matches.is_present("verbose") {
+    Verbosity::Verbose
+  } else {
+    Verbosity::Normal
+  }
+}
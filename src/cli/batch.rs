@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::generate::{self, Options};
+use super::{print_msg, read_input_file, Verbosity, STDIO_FILE_NAME};
+use llmaker::{config, error};
+
+// 複数の文法ファイルやディレクトリから、入力ファイルと出力先の組を作る
+// ディレクトリの場合はその下の全ての.mkrファイルを対象にし、--out-dirの下に同じ構造で書き出す
+pub fn get_batch_targets(
+  input_file_names: &[&str],
+  out_dir_opt: Option<&str>,
+  extension: &str,
+) -> Result<Vec<(String, String)>, error::Error> {
+  let mut targets = Vec::new();
+  for input_file_name in input_file_names.iter() {
+    if *input_file_name == STDIO_FILE_NAME {
+      return Err(error::Error::OptionError(
+        error::OptionError::NotAllowedWithMultipleInputs("'-' (stdin)".to_string()),
+      ));
+    }
+    let input_path = Path::new(input_file_name);
+    let pairs: Vec<(PathBuf, PathBuf)> = if input_path.is_dir() {
+      config::find_mkr_files(input_path)?
+        .into_iter()
+        .map(|path| {
+          let output_path = match out_dir_opt {
+            Some(out_dir) => Path::new(out_dir).join(path.strip_prefix(input_path).unwrap()),
+            None => path.clone(),
+          };
+          (path, output_path.with_extension(extension))
+        })
+        .collect()
+    } else {
+      let output_path = match (out_dir_opt, input_path.file_name()) {
+        (Some(out_dir), Some(file_name)) => Path::new(out_dir).join(file_name),
+        (None, Some(_)) => input_path.to_path_buf(),
+        (_, None) => {
+          return Err(error::Error::OptionError(
+            error::OptionError::BrokenInputFilePath(input_file_name.to_string()),
+          ))
+        }
+      };
+      vec![(
+        input_path.to_path_buf(),
+        output_path.with_extension(extension),
+      )]
+    };
+    for (input_path, output_path) in pairs.into_iter() {
+      targets.push((
+        input_path.display().to_string(),
+        output_path.display().to_string(),
+      ))
+    }
+  }
+  // 別々のスレッドが同じファイルに書き込まないように、出力先が重なっていればエラーにする
+  let mut output_map: HashMap<&String, &String> = HashMap::new();
+  for (input_file_name, output_file_name) in targets.iter() {
+    if let Some(other_input_file_name) = output_map.insert(output_file_name, input_file_name) {
+      return Err(error::Error::OptionError(
+        error::OptionError::DuplicateOutputFile(
+          other_input_file_name.to_string(),
+          input_file_name.to_string(),
+          output_file_name.to_string(),
+        ),
+      ));
+    }
+  }
+  Ok(targets)
+}
+
+// 1つの文法ファイルを処理する
+// エラーの表示に使うため、読み込めていれば入力の中身も一緒に返す
+fn batch_sub(
+  input_file_name: &str,
+  output_file_name: &str,
+  options: &Options,
+) -> Result<(), (error::Error, Option<String>)> {
+  let contents = read_input_file(input_file_name).map_err(|e| (e, None))?;
+  if let Some(parent) = Path::new(output_file_name).parent() {
+    if std::fs::create_dir_all(parent).is_err() {
+      return Err((
+        error::Error::OptionError(error::OptionError::BrokenOutputFile(
+          output_file_name.to_string(),
+        )),
+        None,
+      ));
+    }
+  }
+  let sub_options = Options {
+    output_file_name_opt: Some(output_file_name),
+    verbosity: Verbosity::Quiet,
+    ..options.clone()
+  };
+  match generate::generate(input_file_name, &contents, &sub_options) {
+    Ok(()) => Ok(()),
+    Err(e) => Err((e, Some(contents))),
+  }
+}
+
+// 複数の文法ファイルをjobs個のスレッドで処理する
+// 途中で失敗しても全てのファイルを処理し、最後にエラーをまとめて表示する
+// 1つでも失敗していれば終了コード1で終了する
+pub fn batch(input_file_names: &[&str], out_dir_opt: Option<&str>, jobs: usize, options: &Options) {
+  let targets = match get_batch_targets(input_file_names, out_dir_opt, options.emit.extension()) {
+    Ok(targets) => targets,
+    Err(e) => error::print_error_msg(e, None, None),
+  };
+  let next_index = AtomicUsize::new(0);
+  let mut results = std::thread::scope(|scope| {
+    let handles: Vec<_> = (0..jobs.max(1))
+      .map(|_| {
+        scope.spawn(|| {
+          let mut results = Vec::new();
+          loop {
+            let i = next_index.fetch_add(1, Ordering::SeqCst);
+            match targets.get(i) {
+              Some((input_file_name, output_file_name)) => {
+                results.push((i, batch_sub(input_file_name, output_file_name, options)))
+              }
+              None => break,
+            }
+          }
+          results
+        })
+      })
+      .collect();
+    handles
+      .into_iter()
+      .flat_map(|handle| handle.join().unwrap())
+      .collect::<Vec<_>>()
+  });
+  results.sort_by_key(|(i, _)| *i);
+  let is_quiet = options.verbosity == Verbosity::Quiet;
+  let mut failed_count = 0;
+  for (i, result) in results.into_iter() {
+    let (input_file_name, output_file_name) = &targets[i];
+    match result {
+      Ok(()) if options.is_verify => print_msg(
+        is_quiet,
+        &format!("ok: '{}' is up to date", output_file_name),
+      ),
+      Ok(()) => print_msg(
+        is_quiet,
+        &format!("ok: '{}' -> '{}'", input_file_name, output_file_name),
+      ),
+      Err((e, contents_opt)) => {
+        failed_count += 1;
+        eprintln!(
+          "{}",
+          error::make_error_msg(e, Some(input_file_name), contents_opt.as_deref())
+        )
+      }
+    }
+  }
+  if failed_count > 0 {
+    eprintln!("{} of {} grammar files failed", failed_count, targets.len());
+    std::process::exit(1)
+  }
+}
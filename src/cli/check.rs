@@ -0,0 +1,21 @@
+use super::{get_display_name, print_msg, print_trace_analysis, Verbosity};
+use llmaker::{backend, error, frontend};
+
+// 出力はせずに、生成の際に行う全ての検査だけを行う
+pub fn check(
+  input_file_name: &str,
+  contents: &str,
+  verbosity: Verbosity,
+  is_trace_analysis: bool,
+) -> Result<(), error::Error> {
+  let input_file_name = get_display_name(input_file_name);
+  let ast = frontend::get_ast(contents)?;
+  print_trace_analysis(is_trace_analysis, &ast);
+  let _ = backend::to_string(ast.clone(), input_file_name, contents)?;
+  backend::check_analysis(&ast)?;
+  print_msg(
+    verbosity == Verbosity::Quiet,
+    &format!("no problems found in '{}'", input_file_name),
+  );
+  Ok(())
+}
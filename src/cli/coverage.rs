@@ -0,0 +1,30 @@
+use super::{get_display_name, read_input_file};
+use llmaker::{backend, error, frontend};
+
+// --coverageを付けて生成したパーサが書き出した回数を、文法ファイル上の選択肢の位置と合わせて表示する
+pub fn coverage(
+  input_file_name: &str,
+  contents: &str,
+  count_file_names: &[&str],
+  is_uncovered_only: bool,
+) -> Result<(), error::Error> {
+  let display_name = get_display_name(input_file_name);
+  let ast = frontend::get_ast(contents)?;
+  let mut count_lst = Vec::new();
+  for count_file_name in count_file_names.iter() {
+    let count_contents = read_input_file(count_file_name)?;
+    match backend::coverage::parse_coverage_dump(&count_contents) {
+      Ok(mut lst) => count_lst.append(&mut lst),
+      Err(e) => error::print_error_msg(e, Some(count_file_name), Some(&count_contents)),
+    }
+  }
+  let report = backend::coverage::make_coverage_report_str(
+    &ast,
+    display_name,
+    contents,
+    &count_lst,
+    is_uncovered_only,
+  )?;
+  print!("{}", report);
+  Ok(())
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use super::batch::get_batch_targets;
+use super::STDIO_FILE_NAME;
+use super::{get_display_name, print_msg, read_input_file, run, write_file, Verbosity};
+use llmaker::{diff, error, frontend};
+
+// 文法ファイルを整形して書き戻す（標準入力のときは標準出力に書き出す）
+// --checkのときは書き戻さずに、整形済みでなければ差分をエラーとして返す
+pub fn fmt(
+  input_file_name: &str,
+  contents: &str,
+  is_check: bool,
+  verbosity: Verbosity,
+) -> Result<(), error::Error> {
+  let display_name = get_display_name(input_file_name);
+  let formatted_str = frontend::fmt::format_str(contents)?;
+  let is_quiet = verbosity == Verbosity::Quiet;
+  if is_check {
+    if formatted_str != contents {
+      let diff_str = diff::make_diff_str(display_name, contents, "(formatted)", &formatted_str);
+      return Err(error::Error::VerifyError(
+        error::VerifyError::UnformattedFile(display_name.to_string(), diff_str),
+      ));
+    }
+    print_msg(is_quiet, &format!("ok: '{}' is formatted", display_name));
+  } else if input_file_name == STDIO_FILE_NAME {
+    write_file(STDIO_FILE_NAME.to_string(), formatted_str);
+  } else if formatted_str != contents {
+    write_file(input_file_name.to_string(), formatted_str);
+    print_msg(is_quiet, &format!("formatted '{}'", display_name));
+  } else if verbosity == Verbosity::Verbose {
+    print_msg(
+      is_quiet,
+      &format!("'{}' is already formatted", display_name),
+    );
+  }
+  Ok(())
+}
+
+// 複数の文法ファイルやディレクトリをまとめて整形する
+// 途中で失敗しても全てのファイルを処理し、1つでも失敗していれば終了コード1で終了する
+pub fn fmt_all(input_file_names: &[&str], is_check: bool, verbosity: Verbosity) {
+  if input_file_names.len() <= 1 && !input_file_names.iter().any(|s| Path::new(s).is_dir()) {
+    run(
+      input_file_names.first().copied(),
+      |input_file_name, contents| fmt(input_file_name, contents, is_check, verbosity),
+    );
+    return;
+  }
+  let targets = match get_batch_targets(input_file_names, None, "mkr") {
+    Ok(targets) => targets,
+    Err(e) => error::print_error_msg(e, None, None),
+  };
+  let mut failed_count = 0;
+  for (input_file_name, _) in targets.iter() {
+    let result = read_input_file(input_file_name)
+      .map_err(|e| (e, None))
+      .and_then(|contents| {
+        fmt(input_file_name, &contents, is_check, verbosity).map_err(|e| (e, Some(contents)))
+      });
+    if let Err((e, contents_opt)) = result {
+      failed_count += 1;
+      eprintln!(
+        "{}",
+        error::make_error_msg(e, Some(input_file_name), contents_opt.as_deref())
+      )
+    }
+  }
+  if failed_count > 0 {
+    eprintln!("{} of {} grammar files failed", failed_count, targets.len());
+    std::process::exit(1)
+  }
+}
@@ -0,0 +1,274 @@
+use std::io::prelude::*;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::{get_display_name, read_input_file, STDIO_FILE_NAME};
+use super::{print_line, print_msg, print_trace_analysis, write_file, Verbosity};
+use llmaker::{backend, diff, error, frontend, types};
+
+// 何を出力するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+  Rust,
+  Dot,
+  Railroad,
+  Tables,
+  TablesJson,
+  AstJson,
+}
+
+pub const EMIT_NAMES: [&str; 6] = [
+  "rust",
+  "dot",
+  "railroad",
+  "tables",
+  "tables-json",
+  "ast-json",
+];
+
+impl Emit {
+  pub fn from_name(name: &str) -> Emit {
+    match name {
+      "dot" => Emit::Dot,
+      "railroad" => Emit::Railroad,
+      "tables" => Emit::Tables,
+      "tables-json" => Emit::TablesJson,
+      "ast-json" => Emit::AstJson,
+      _ => Emit::Rust,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Emit::Rust => "rust",
+      Emit::Dot => "dot",
+      Emit::Railroad => "railroad",
+      Emit::Tables => "tables",
+      Emit::TablesJson => "tables-json",
+      Emit::AstJson => "ast-json",
+    }
+  }
+
+  // 出力先のファイル名を指定しなかったときに使う拡張子
+  pub fn extension(self) -> &'static str {
+    match self {
+      Emit::Rust => "rs",
+      Emit::Dot => "dot",
+      Emit::Railroad => "html",
+      Emit::Tables => "tables.txt",
+      Emit::TablesJson => "tables.json",
+      Emit::AstJson => "ast.json",
+    }
+  }
+}
+
+// 1つの文法ファイルを処理するときの設定
+#[derive(Debug, Clone)]
+pub struct Options<'a> {
+  pub output_file_name_opt: Option<&'a str>,
+  pub source_map_file_name_opt: Option<&'a str>,
+  pub emit: Emit,
+  pub is_verify: bool,
+  pub verbosity: Verbosity,
+  pub is_trace_analysis: bool,
+  pub generate_options: backend::GenerateOptions,
+}
+
+// 左再帰や選択肢の衝突があっても生成は続け、警告だけを表示する
+fn print_analysis_warning(ast: &types::Term, input_file_name: &str, contents: &str) {
+  if let Err(e) = backend::check_analysis(ast) {
+    error::print_warning_msg(e, Some(input_file_name), Some(contents));
+  }
+}
+
+pub fn get_output_file_name(
+  input_file_name: &str,
+  output_file_name_opt: Option<&str>,
+  extension: &str,
+) -> Result<String, error::Error> {
+  match output_file_name_opt {
+    Some(path) => Ok(path.to_string()),
+    None if input_file_name == STDIO_FILE_NAME => Ok(STDIO_FILE_NAME.to_string()),
+    None => {
+      let path = Path::new(input_file_name);
+      let parent_opt = path.parent();
+      let file_steam_opt = path.file_stem();
+      let new_path = match (parent_opt, file_steam_opt) {
+        (Some(parent), Some(file_steam)) => Ok(parent.join(file_steam)),
+        (None, Some(file_steam)) => Ok((Path::new("")).join(file_steam)),
+        _ => Err(error::Error::OptionError(
+          error::OptionError::BrokenInputFilePath(input_file_name.to_string()),
+        )),
+      }?;
+      Ok(format!("{}.{}", new_path.to_str().unwrap(), extension))
+    }
+  }
+}
+
+// 出力先のファイルから上のディレクトリを辿ってrustfmtの設定ファイルとCargo.tomlを探し、
+// 設定ファイルとCargo.tomlに書かれたeditionを使ってrustfmtで整形する
+// Cargo.tomlが無いか、editionが書かれていなければ2015として扱う（cargoと同じ）
+// rustfmtが無い場合や整形に失敗した場合はNoneを返す
+fn format_with_rustfmt(text: &str, output_file_name: &str) -> Option<String> {
+  let output_path = Path::new(output_file_name).canonicalize().ok()?;
+  let edition = output_path
+    .ancestors()
+    .skip(1)
+    .map(|dir| dir.join("Cargo.toml"))
+    .find(|path| path.is_file())
+    .and_then(|path| std::fs::read_to_string(path).ok())
+    .and_then(|cargo_toml| get_edition(&cargo_toml))
+    .unwrap_or_else(|| "2015".to_string());
+  let mut command = Command::new("rustfmt");
+  command.args(["--emit", "stdout", "--edition", &edition]);
+  let config_path_opt = output_path.ancestors().skip(1).find_map(|dir| {
+    ["rustfmt.toml", ".rustfmt.toml"]
+      .iter()
+      .map(|name| dir.join(name))
+      .find(|path| path.is_file())
+  });
+  if let Some(config_path) = config_path_opt {
+    command.arg("--config-path").arg(config_path);
+  }
+  let mut child = command
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .ok()?;
+  child.stdin.take()?.write_all(text.as_bytes()).ok()?;
+  let output = child.wait_with_output().ok()?;
+  if output.status.success() {
+    String::from_utf8(output.stdout).ok()
+  } else {
+    None
+  }
+}
+
+// Cargo.tomlの[package]に書かれた`edition = "2018"`のようなeditionを取り出す
+fn get_edition(cargo_toml: &str) -> Option<String> {
+  let mut is_package = false;
+  for line in cargo_toml.lines() {
+    let line = line.trim();
+    if line.starts_with('[') {
+      is_package = line == "[package]";
+      continue;
+    }
+    if !is_package {
+      continue;
+    }
+    if let Some((key, value)) = line.split_once('=') {
+      if key.trim() == "edition" {
+        return Some(value.trim().trim_matches('"').to_string());
+      }
+    }
+  }
+  None
+}
+
+// 生成したコードと出力先のファイルの中身が一致するか確認する
+// 一致しなければ、rustfmtで整形したものとも比べる
+fn verify(output_file_name: &str, output_str: &str) -> Result<(), error::Error> {
+  let old_str = match read_input_file(output_file_name) {
+    Ok(s) => Ok(s),
+    Err(_) => Err(error::Error::VerifyError(
+      error::VerifyError::NotFoundOutputFile(output_file_name.to_string()),
+    )),
+  }?;
+  if old_str == output_str {
+    return Ok(());
+  }
+  let new_str = match format_with_rustfmt(output_str, output_file_name) {
+    Some(formatted_str) => formatted_str,
+    None => output_str.to_string(),
+  };
+  if old_str == new_str {
+    Ok(())
+  } else {
+    let diff_str = diff::make_diff_str(output_file_name, &old_str, "(generated)", &new_str);
+    Err(error::Error::VerifyError(
+      error::VerifyError::StaleOutputFile(output_file_name.to_string(), diff_str),
+    ))
+  }
+}
+
+fn make_output_str(
+  options: &Options,
+  ast: &types::Term,
+  input_file_name: &str,
+  contents: &str,
+) -> Result<String, error::Error> {
+  match options.emit {
+    Emit::Rust => backend::to_string_with_options(
+      ast.clone(),
+      input_file_name,
+      contents,
+      &options.generate_options,
+    ),
+    Emit::Dot => Ok(backend::dot::make_dot_str(ast)),
+    Emit::Railroad => Ok(backend::railroad::make_railroad_str(ast)),
+    Emit::Tables => Ok(backend::tables::make_tables_str(ast)),
+    Emit::TablesJson => Ok(backend::tables::make_tables_json_str(ast)),
+    Emit::AstJson => Ok(backend::ast_json::make_ast_json_str(ast, contents)),
+  }
+}
+
+// 1つの文法ファイルから、--emitで指定したものを出力先に書き出す（--verifyのときは比べるだけ）
+pub fn generate(
+  input_file_name: &str,
+  contents: &str,
+  options: &Options,
+) -> Result<(), error::Error> {
+  let output_file_name = get_output_file_name(
+    input_file_name,
+    options.output_file_name_opt,
+    options.emit.extension(),
+  )?;
+  let is_quiet = options.verbosity == Verbosity::Quiet || output_file_name == STDIO_FILE_NAME;
+  let is_verbose = options.verbosity == Verbosity::Verbose && !is_quiet;
+  let input_file_name = get_display_name(input_file_name);
+  print_line(is_quiet);
+  print_msg(is_quiet, &format!("target file: '{}'", output_file_name));
+  print_line(is_quiet);
+  print_msg(is_quiet, &format!("parsing '{}' ...", input_file_name));
+  let ast = frontend::get_ast(contents)?;
+  print_msg(is_quiet, "dune.");
+  let (_, (_, token_lst), bnfs) = &ast;
+  print_msg(
+    !is_verbose,
+    &format!("{} rules, {} tokens", bnfs.len(), token_lst.len()),
+  );
+  print_trace_analysis(options.is_trace_analysis, &ast);
+  print_line(is_quiet);
+  print_msg(is_quiet, "making texts ...");
+  let output_str = make_output_str(options, &ast, input_file_name, contents)?;
+  if options.emit == Emit::Rust {
+    print_analysis_warning(&ast, input_file_name, contents);
+  }
+  print_msg(is_quiet, "dune.");
+  print_msg(
+    !is_verbose,
+    &format!("{} lines generated", output_str.lines().count()),
+  );
+  print_line(is_quiet);
+  if options.is_verify {
+    verify(&output_file_name, &output_str)?;
+    print_msg(is_quiet, &format!("'{}' is up to date", output_file_name));
+    return Ok(());
+  }
+  write_file(output_file_name.clone(), output_str.clone());
+  print_msg(
+    is_quiet,
+    &format!("output written on '{}'", output_file_name),
+  );
+  if let Some(source_map_file_name) = options.source_map_file_name_opt {
+    let source_map_str =
+      backend::source_map::make_source_map_str(&ast, input_file_name, contents, &output_str);
+    write_file(source_map_file_name.to_string(), source_map_str);
+    print_msg(
+      is_quiet,
+      &format!("source map written on '{}'", source_map_file_name),
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,27 @@
+use super::{print_msg, write_file, Verbosity, STDIO_FILE_NAME};
+use llmaker::{backend, error, frontend};
+
+// 規則またはトークンの名前を変えて書き戻す（標準入力のときは標準出力に書き出す）
+pub fn rename(
+  input_file_name: &str,
+  contents: &str,
+  old_name: &str,
+  new_name: &str,
+  verbosity: Verbosity,
+) -> Result<(), error::Error> {
+  let ast = frontend::get_ast(contents)?;
+  let (output_str, count) = backend::symbols::rename(&ast, contents, old_name, new_name)?;
+  if input_file_name == STDIO_FILE_NAME {
+    write_file(STDIO_FILE_NAME.to_string(), output_str);
+  } else {
+    write_file(input_file_name.to_string(), output_str);
+    print_msg(
+      verbosity == Verbosity::Quiet,
+      &format!(
+        "renamed '{}' to '{}' in {} places in '{}'",
+        old_name, new_name, count, input_file_name
+      ),
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,125 @@
+use std::io::prelude::*;
+
+use super::STDIO_FILE_NAME;
+use super::{get_start_rule_name, load_grammar, print_msg, read_input_file, Verbosity};
+use llmaker::{backend, error};
+
+const REPL_HELP: &str = "type token names separated by spaces to parse them, or
+  :rule <name>  parse with another rule
+  :reload       read the grammar file again
+  :help         show this message
+  :quit         exit";
+
+// 1行ずつトークン名の列を読み、文法をそのまま使って読んだ構文木か、読めなかった位置を表示する
+pub fn repl(
+  input_file_name: &str,
+  contents: &str,
+  rule_name_opt: Option<&str>,
+  verbosity: Verbosity,
+) -> Result<(), error::Error> {
+  if input_file_name == STDIO_FILE_NAME {
+    return Err(error::Error::OptionError(
+      error::OptionError::CannotReplStdin,
+    ));
+  }
+  let mut ast = load_grammar(input_file_name, contents)?;
+  let mut rule_name = match rule_name_opt {
+    Some(name) if backend::interpreter::is_defined_rule(&ast, name) => name.to_string(),
+    Some(name) => {
+      return Err(error::Error::ReplError(error::ReplError::NotFoundRule(
+        name.to_string(),
+      )))
+    }
+    None => get_start_rule_name(&ast).unwrap_or_default(),
+  };
+  let is_quiet = verbosity == Verbosity::Quiet;
+  print_msg(
+    is_quiet,
+    &format!("{} (type :help for help)", input_file_name),
+  );
+  let stdin = std::io::stdin();
+  let mut lines = stdin.lock().lines();
+  loop {
+    if !is_quiet {
+      print!("{}> ", rule_name);
+      let _ = std::io::stdout().flush();
+    }
+    let line = match lines.next() {
+      Some(Ok(line)) => line,
+      _ => break,
+    };
+    let mut words = line.split_whitespace();
+    match words.next() {
+      None => (),
+      Some(":quit") | Some(":q") => break,
+      Some(":help") => println!("{}", REPL_HELP),
+      Some(":rule") => match words.next() {
+        Some(name) if backend::interpreter::is_defined_rule(&ast, name) => {
+          rule_name = name.to_string()
+        }
+        Some(name) => eprintln!(
+          "{}",
+          error::make_error_msg(
+            error::Error::ReplError(error::ReplError::NotFoundRule(name.to_string())),
+            Some(input_file_name),
+            None
+          )
+        ),
+        None => println!("{}", rule_name),
+      },
+      // 読み込めなければ前の文法を使い続ける
+      Some(":reload") => match read_input_file(input_file_name) {
+        Ok(contents) => match load_grammar(input_file_name, &contents) {
+          Ok(new_ast) => {
+            ast = new_ast;
+            if !backend::interpreter::is_defined_rule(&ast, &rule_name) {
+              rule_name = get_start_rule_name(&ast).unwrap_or_default()
+            }
+            print_msg(is_quiet, &format!("reloaded '{}'", input_file_name))
+          }
+          Err(e) => eprintln!(
+            "{}",
+            error::make_error_msg(e, Some(input_file_name), Some(&contents))
+          ),
+        },
+        Err(e) => eprintln!("{}", error::make_error_msg(e, Some(input_file_name), None)),
+      },
+      Some(word) if word.starts_with(':') => {
+        eprintln!("unknown command '{}' (type :help for help)", word)
+      }
+      Some(_) => {
+        let tokens: Vec<String> = line.split_whitespace().map(|s| s.to_string()).collect();
+        if !backend::interpreter::is_defined_rule(&ast, &rule_name) {
+          eprintln!(
+            "{}",
+            error::make_error_msg(
+              error::Error::ReplError(error::ReplError::NotFoundRule(rule_name.clone())),
+              Some(input_file_name),
+              None
+            )
+          );
+          continue;
+        }
+        if let Some(tok_name) = backend::interpreter::find_undefined_token(&ast, &tokens) {
+          eprintln!(
+            "{}",
+            error::make_error_msg(
+              error::Error::ReplError(error::ReplError::NotFoundToken(tok_name.clone())),
+              Some(input_file_name),
+              None
+            )
+          );
+          continue;
+        }
+        match backend::interpreter::parse(&ast, &rule_name, &tokens) {
+          Ok(tree) => print!("{}", tree.to_str()),
+          Err(failure) => println!(
+            "{}",
+            backend::grammar_test::failure_to_str(&failure, &tokens)
+          ),
+        }
+      }
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,38 @@
+use super::STDIO_FILE_NAME;
+use super::{get_display_name, get_start_rule_name, load_grammar, write_file, Verbosity};
+use llmaker::{backend, error};
+
+// ランダムなトークン名の列を1行に1つずつ標準出力に書き出す
+pub fn generate_samples(
+  input_file_name: &str,
+  contents: &str,
+  rule_name_opt: Option<&str>,
+  options: &backend::sample::SampleOptions,
+  verbosity: Verbosity,
+) -> Result<(), error::Error> {
+  let display_name = get_display_name(input_file_name);
+  let ast = load_grammar(display_name, contents)?;
+  let rule_name = match rule_name_opt {
+    Some(name) => name.to_string(),
+    None => get_start_rule_name(&ast).unwrap_or_default(),
+  };
+  let sample_lst = backend::sample::generate_samples(&ast, &rule_name, options)?;
+  let mut output = String::new();
+  for tokens in sample_lst.iter() {
+    output.push_str(&tokens.join(" "));
+    output.push('\n');
+  }
+  write_file(STDIO_FILE_NAME.to_string(), output);
+  // 列は標準出力に書き出すので、経過のメッセージは標準エラー出力に出す
+  if verbosity != Verbosity::Quiet {
+    eprintln!("  seed: {}", options.seed);
+    if sample_lst.len() < options.count {
+      eprintln!(
+        "  generated only {} of {} samples",
+        sample_lst.len(),
+        options.count
+      );
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use super::STDIO_FILE_NAME;
+use super::{get_display_name, load_grammar, print_msg, read_input_file, Verbosity};
+use llmaker::{backend, error};
+
+// 文法ファイルと同じ名前で、テストを書いておくファイルの拡張子
+const TEST_FILE_EXTENSION: &str = "tests";
+
+// 文法をそのまま使ってテストを実行し、結果を表示する
+// テストは文法ファイルの中の`//!`で始まるコメントと、拡張子を.testsにした同じ名前のファイルから読む
+// 1つでも失敗していれば終了コード1で終了する
+pub fn test(
+  input_file_name: &str,
+  contents: &str,
+  verbosity: Verbosity,
+) -> Result<(), error::Error> {
+  let display_name = get_display_name(input_file_name);
+  let ast = load_grammar(display_name, contents)?;
+  let mut test_suite_lst = vec![(
+    display_name.to_string(),
+    contents.to_string(),
+    backend::grammar_test::parse_inline_test_cases(contents)?,
+  )];
+  let test_file_path = Path::new(input_file_name).with_extension(TEST_FILE_EXTENSION);
+  if input_file_name != STDIO_FILE_NAME && test_file_path.is_file() {
+    let test_file_name = test_file_path.display().to_string();
+    let test_contents = read_input_file(&test_file_name)?;
+    match backend::grammar_test::parse_test_file(&test_contents) {
+      Ok(test_case_lst) => test_suite_lst.push((test_file_name, test_contents, test_case_lst)),
+      Err(e) => error::print_error_msg(e, Some(&test_file_name), Some(&test_contents)),
+    }
+  }
+  let is_quiet = verbosity == Verbosity::Quiet;
+  let mut passed_count = 0;
+  let mut failed_count = 0;
+  for (file_name, file_contents, test_case_lst) in test_suite_lst.iter() {
+    for test_case in test_case_lst.iter() {
+      let result = match backend::grammar_test::run_test_case(&ast, test_case) {
+        Ok(result) => result,
+        Err(e) => error::print_error_msg(e, Some(file_name), Some(file_contents)),
+      };
+      let name = format!("{}:{}: {}", file_name, test_case.line, test_case.to_str());
+      let reason = match result {
+        backend::grammar_test::TestResult::Passed => {
+          passed_count += 1;
+          print_msg(is_quiet, &format!("ok: {}", name));
+          continue;
+        }
+        backend::grammar_test::TestResult::UnexpectedlyAccepted => {
+          "the tokens were accepted".to_string()
+        }
+        backend::grammar_test::TestResult::UnexpectedlyRejected(failure) => {
+          backend::grammar_test::failure_to_str(&failure, &test_case.tokens)
+        }
+      };
+      failed_count += 1;
+      println!("  FAILED: {}\n    {}", name, reason);
+    }
+  }
+  if passed_count + failed_count == 0 {
+    print_msg(is_quiet, &format!("no tests found for '{}'", display_name));
+  } else {
+    print_msg(
+      is_quiet,
+      &format!("{} passed, {} failed", passed_count, failed_count),
+    );
+  }
+  if failed_count > 0 {
+    std::process::exit(1)
+  }
+  Ok(())
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use super::generate::{self, get_output_file_name, Options};
+use super::{print_msg, read_input_file, Verbosity, STDIO_FILE_NAME};
+use llmaker::error;
+
+// --watchで入力ファイルの更新を確認する間隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+// 入力ファイルの更新日時を監視し、変更されるたびにパーサを生成し直す
+// エラーがあっても終了せずに表示だけして、次の変更を待つ
+pub fn watch(input_file_name: &str, options: &Options) -> ! {
+  if input_file_name == STDIO_FILE_NAME {
+    error::print_error_msg(
+      error::Error::OptionError(error::OptionError::CannotWatchStdin),
+      None,
+      None,
+    )
+  }
+  let output_file_name = match get_output_file_name(
+    input_file_name,
+    options.output_file_name_opt,
+    options.emit.extension(),
+  ) {
+    Ok(output_file_name) => output_file_name,
+    Err(e) => error::print_error_msg(e, Some(input_file_name), None),
+  };
+  let is_quiet = options.verbosity == Verbosity::Quiet || output_file_name == STDIO_FILE_NAME;
+  let sub_options = Options {
+    output_file_name_opt: Some(&output_file_name),
+    verbosity: Verbosity::Quiet,
+    ..options.clone()
+  };
+  print_msg(
+    is_quiet,
+    &format!("watching '{}' (press Ctrl-C to stop)", input_file_name),
+  );
+  let mut last_modified_opt = None;
+  let mut is_first = true;
+  loop {
+    let modified_opt = std::fs::metadata(input_file_name)
+      .and_then(|metadata| metadata.modified())
+      .ok();
+    if is_first || modified_opt != last_modified_opt {
+      is_first = false;
+      last_modified_opt = modified_opt;
+      match read_input_file(input_file_name) {
+        Ok(contents) => match generate::generate(input_file_name, &contents, &sub_options) {
+          Ok(()) => print_msg(
+            is_quiet,
+            &format!("ok: '{}' -> '{}'", input_file_name, output_file_name),
+          ),
+          Err(e) => eprintln!(
+            "{}",
+            error::make_error_msg(e, Some(input_file_name), Some(&contents))
+          ),
+        },
+        Err(e) => eprintln!("{}", error::make_error_msg(e, Some(input_file_name), None)),
+      }
+    }
+    std::thread::sleep(WATCH_INTERVAL);
+  }
+}
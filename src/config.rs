@@ -0,0 +1,228 @@
+use super::backend;
+use super::error;
+use super::frontend;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// build.rsから文法ファイルをまとめて処理するための設定
+///
+/// ```no_run
+/// // build.rsのmain関数の中で
+/// llmaker::Config::new().process_dir("src").unwrap();
+/// ```
+///
+/// `src/foo/bar.mkr`から生成されたコードは`$OUT_DIR/foo/bar.rs`に書き出されるので、
+/// `include!(concat!(env!("OUT_DIR"), "/foo/bar.rs"));`のようにして読み込む。
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+  out_dir: Option<PathBuf>,
+  generate_options: backend::GenerateOptions,
+}
+
+impl Config {
+  pub fn new() -> Config {
+    Config {
+      out_dir: None,
+      generate_options: backend::GenerateOptions::default(),
+    }
+  }
+
+  /// 出力先のディレクトリを指定する（指定しなければ`OUT_DIR`環境変数の値を使う）
+  pub fn out_dir<P: AsRef<Path>>(mut self, out_dir: P) -> Config {
+    self.out_dir = Some(out_dir.as_ref().to_path_buf());
+    self
+  }
+
+  /// 生成するパーサで、各選択肢が選ばれた回数を数えるようにする（`--coverage`と同じ）
+  pub fn coverage(mut self, is_coverage: bool) -> Config {
+    self.generate_options.is_coverage = is_coverage;
+    self
+  }
+
+  /// 生成するパーサで、規則とトークンの読み込みのイベントを`ParseTracer`に送るようにする（`--trace`と同じ）
+  pub fn trace(mut self, is_trace: bool) -> Config {
+    self.generate_options.is_trace = is_trace;
+    self
+  }
+
+  /// アクションの代わりに、具象構文木の`Node`を返すパーサを生成する（`--cst`と同じ）
+  pub fn cst(mut self, is_cst: bool) -> Config {
+    self.generate_options.is_cst = is_cst;
+    self
+  }
+
+  /// `dir`以下の全ての`.mkr`ファイルを処理する
+  ///
+  /// 生成したコードが出力先のファイルと同じときは書き込まない。
+  /// エラーは`cargo:warning=`として表示し、最初のエラーを返す。
+  pub fn process_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), error::Error> {
+    let dir = dir.as_ref();
+    let out_dir = self.get_out_dir()?;
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let mut first_err_opt = None;
+    for input_path in find_mkr_files(dir)?.iter() {
+      let relative_path = input_path.strip_prefix(dir).unwrap_or(input_path);
+      let output_path = out_dir.join(relative_path).with_extension("rs");
+      if let Err(e) = process_file(input_path, &output_path, &self.generate_options) {
+        if first_err_opt.is_none() {
+          first_err_opt = Some(e)
+        }
+      }
+    }
+    match first_err_opt {
+      None => Ok(()),
+      Some(e) => Err(e),
+    }
+  }
+
+  /// 1つの文法ファイルを処理して、出力先のディレクトリに書き出す
+  pub fn process_file<P: AsRef<Path>>(&self, input_path: P) -> Result<(), error::Error> {
+    let input_path = input_path.as_ref();
+    let out_dir = self.get_out_dir()?;
+    let output_path = match input_path.file_name() {
+      Some(file_name) => Ok(out_dir.join(file_name).with_extension("rs")),
+      None => Err(error::Error::OptionError(
+        error::OptionError::BrokenInputFilePath(input_path.display().to_string()),
+      )),
+    }?;
+    process_file(input_path, &output_path, &self.generate_options)
+  }
+
+  fn get_out_dir(&self) -> Result<PathBuf, error::Error> {
+    match &self.out_dir {
+      Some(out_dir) => Ok(out_dir.clone()),
+      None => match std::env::var_os("OUT_DIR") {
+        Some(out_dir) => Ok(PathBuf::from(out_dir)),
+        None => Err(error::Error::OptionError(
+          error::OptionError::NoOutputDirectory,
+        )),
+      },
+    }
+  }
+}
+
+/// `dir`以下の全ての`.mkr`ファイルを、パスの順に並べて返す
+pub fn find_mkr_files(dir: &Path) -> Result<Vec<PathBuf>, error::Error> {
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => Ok(entries),
+    Err(_) => Err(error::Error::OptionError(
+      error::OptionError::NotFoundInputDirectory(dir.display().to_string()),
+    )),
+  }?;
+  let mut paths: Vec<PathBuf> = entries
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .collect();
+  paths.sort();
+  let mut mkr_files = Vec::new();
+  for path in paths.into_iter() {
+    if path.is_dir() {
+      mkr_files.append(&mut find_mkr_files(&path)?)
+    } else if path.extension() == Some(OsStr::new("mkr")) {
+      mkr_files.push(path)
+    }
+  }
+  Ok(mkr_files)
+}
+
+// 出力先のファイルが既に同じ内容なら書き込まず、更新日時を変えない
+// 更新日時だけで比べると、llmakerのバージョンや設定が変わったときに生成し直されないので、毎回生成して比べる
+fn is_up_to_date(output_path: &Path, output_str: &str) -> bool {
+  match fs::read_to_string(output_path) {
+    Ok(old_str) => old_str == output_str,
+    Err(_) => false,
+  }
+}
+
+fn process_file(
+  input_path: &Path,
+  output_path: &Path,
+  generate_options: &backend::GenerateOptions,
+) -> Result<(), error::Error> {
+  println!("cargo:rerun-if-changed={}", input_path.display());
+  let input_file_name = input_path.display().to_string();
+  let contents = match fs::read_to_string(input_path) {
+    Ok(contents) => Ok(contents),
+    Err(_) => Err(error::Error::OptionError(
+      error::OptionError::NotFoundInputFileName(input_file_name.clone()),
+    )),
+  }?;
+  let output_str_res = frontend::get_ast(&contents).and_then(|ast| {
+    let output_str =
+      backend::to_string_with_options(ast.clone(), &input_file_name, &contents, generate_options)?;
+    // 左再帰や選択肢の衝突があっても生成は続ける
+    if let Err(e) = backend::check_analysis(&ast) {
+      print_cargo_warning(e, &input_file_name, &contents);
+    }
+    Ok(output_str)
+  });
+  let output_str = match output_str_res {
+    Ok(output_str) => Ok(output_str),
+    Err(e) => {
+      print_cargo_warning(e.clone(), &input_file_name, &contents);
+      Err(e)
+    }
+  }?;
+  if is_up_to_date(output_path, &output_str) {
+    return Ok(());
+  }
+  let write_res = match output_path.parent() {
+    Some(parent) => fs::create_dir_all(parent),
+    None => Ok(()),
+  }
+  .and_then(|()| fs::write(output_path, output_str));
+  match write_res {
+    Ok(()) => Ok(()),
+    Err(_) => Err(error::Error::OptionError(
+      error::OptionError::BrokenOutputFile(output_path.display().to_string()),
+    )),
+  }
+}
+
+// cargoの警告として1行ずつ表示する
+fn print_cargo_warning(err: error::Error, input_file_name: &str, contents: &str) {
+  let msg = error::make_error_msg(err, Some(input_file_name), Some(contents));
+  for line in msg.lines() {
+    println!("cargo:warning={}", line);
+  }
+}
+
+#[test]
+fn check_process_dir() {
+  // 並列や繰り返しの実行で衝突しないように、プロセスごとに別のディレクトリを使う
+  let dir = std::env::temp_dir().join(format!("llmaker_check_process_dir_{}", std::process::id()));
+  let in_dir = dir.join("src");
+  let out_dir = dir.join("out");
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(in_dir.join("foo")).unwrap();
+  fs::write(
+    in_dir.join("foo").join("bar.mkr"),
+    "grammar;
+extern {
+  enum \"char\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+};
+",
+  )
+  .unwrap();
+  let output_path = out_dir.join("foo").join("bar.rs");
+  assert!(Config::new().out_dir(&out_dir).process_dir(&in_dir).is_ok());
+  assert!(!fs::read_to_string(&output_path)
+    .unwrap()
+    .contains("coverage_counts"));
+  // 文法ファイルが変わっていなくても、設定が変われば生成し直す
+  assert!(Config::new()
+    .out_dir(&out_dir)
+    .coverage(true)
+    .process_dir(&in_dir)
+    .is_ok());
+  assert!(fs::read_to_string(&output_path)
+    .unwrap()
+    .contains("coverage_counts"));
+  let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,171 @@
+// 行単位の差分をunified diffの形式で作る
+
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Edit<'a> {
+  Same(&'a str),
+  Delete(&'a str),
+  Insert(&'a str),
+}
+
+// 最長共通部分列の表の大きさの上限
+// 先頭と末尾の共通部分を除いてもこれを超えるときは、間を全て削除して全て追加したものとする
+const MAX_LCS_TABLE_SIZE: usize = 1 << 22;
+
+// 最長共通部分列を使って編集列を求める
+// 表はO(n·m)の大きさになるので、先頭と末尾の共通部分を除いた間の行だけで作る
+fn make_edits<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<Edit<'a>> {
+  let prefix_len = old_lines
+    .iter()
+    .zip(new_lines.iter())
+    .take_while(|(old_line, new_line)| old_line == new_line)
+    .count();
+  let suffix_len = old_lines[prefix_len..]
+    .iter()
+    .rev()
+    .zip(new_lines[prefix_len..].iter().rev())
+    .take_while(|(old_line, new_line)| old_line == new_line)
+    .count();
+  let old_mid = &old_lines[prefix_len..old_lines.len() - suffix_len];
+  let new_mid = &new_lines[prefix_len..new_lines.len() - suffix_len];
+  let mut edits: Vec<Edit> = old_lines[..prefix_len]
+    .iter()
+    .map(|line| Edit::Same(line))
+    .collect();
+  if (old_mid.len() + 1).saturating_mul(new_mid.len() + 1) <= MAX_LCS_TABLE_SIZE {
+    edits.append(&mut make_lcs_edits(old_mid, new_mid));
+  } else {
+    edits.extend(old_mid.iter().map(|line| Edit::Delete(line)));
+    edits.extend(new_mid.iter().map(|line| Edit::Insert(line)));
+  }
+  edits.extend(
+    old_lines[old_lines.len() - suffix_len..]
+      .iter()
+      .map(|line| Edit::Same(line)),
+  );
+  edits
+}
+
+fn make_lcs_edits<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<Edit<'a>> {
+  let n = old_lines.len();
+  let m = new_lines.len();
+  let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+  for i in (0..n).rev() {
+    for j in (0..m).rev() {
+      lcs[i][j] = if old_lines[i] == new_lines[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        std::cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+      }
+    }
+  }
+  let mut edits = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < n || j < m {
+    if i < n && j < m && old_lines[i] == new_lines[j] {
+      edits.push(Edit::Same(old_lines[i]));
+      i += 1;
+      j += 1;
+    } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+      edits.push(Edit::Delete(old_lines[i]));
+      i += 1;
+    } else {
+      edits.push(Edit::Insert(new_lines[j]));
+      j += 1;
+    }
+  }
+  edits
+}
+
+pub fn make_diff_str(old_name: &str, old: &str, new_name: &str, new: &str) -> String {
+  let old_lines: Vec<&str> = old.lines().collect();
+  let new_lines: Vec<&str> = new.lines().collect();
+  let edits = make_edits(&old_lines, &new_lines);
+  // 各編集の直前までの旧・新の行数
+  let mut positions = Vec::new();
+  let (mut old_pos, mut new_pos) = (0, 0);
+  for edit in edits.iter() {
+    positions.push((old_pos, new_pos));
+    match edit {
+      Edit::Same(_) => {
+        old_pos += 1;
+        new_pos += 1;
+      }
+      Edit::Delete(_) => old_pos += 1,
+      Edit::Insert(_) => new_pos += 1,
+    }
+  }
+  // 変更のある編集の位置を、間の変更の無い行がCONTEXT_LINES * 2以下なら繋げて塊にする
+  let mut hunks: Vec<(usize, usize)> = Vec::new();
+  for (k, edit) in edits.iter().enumerate() {
+    if let Edit::Same(_) = edit {
+      continue;
+    }
+    match hunks.last_mut() {
+      Some((_, last)) if k - *last <= CONTEXT_LINES * 2 + 1 => *last = k,
+      _ => hunks.push((k, k)),
+    }
+  }
+  let mut s = format!("--- {}\n+++ {}\n", old_name, new_name);
+  for (first, last) in hunks.iter() {
+    let start = first.saturating_sub(CONTEXT_LINES);
+    let end = std::cmp::min(last + CONTEXT_LINES + 1, edits.len());
+    let mut hunk_s = String::new();
+    let (mut old_len, mut new_len) = (0, 0);
+    for edit in edits[start..end].iter() {
+      match edit {
+        Edit::Same(line) => {
+          hunk_s.push_str(&format!(" {}\n", line));
+          old_len += 1;
+          new_len += 1;
+        }
+        Edit::Delete(line) => {
+          hunk_s.push_str(&format!("-{}\n", line));
+          old_len += 1;
+        }
+        Edit::Insert(line) => {
+          hunk_s.push_str(&format!("+{}\n", line));
+          new_len += 1;
+        }
+      }
+    }
+    let (hunk_old_start, hunk_new_start) = positions[start];
+    s.push_str(&format!(
+      "@@ -{},{} +{},{} @@\n{}",
+      hunk_old_start + 1,
+      old_len,
+      hunk_new_start + 1,
+      new_len,
+      hunk_s
+    ));
+  }
+  s
+}
+
+#[test]
+fn check_make_diff_str() {
+  assert_eq!(
+    make_diff_str(
+      "a",
+      "1\n2\n3\n4\n5\n6\n7\n8\n9\n",
+      "b",
+      "1\n2\n3\n4\nfive\n6\n7\n8\n9\n"
+    ),
+    "--- a\n+++ b\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n".to_string()
+  );
+  assert_eq!(
+    make_diff_str("a", "x\n", "b", "x\n"),
+    "--- a\n+++ b\n".to_string()
+  );
+  // 大きなファイルでも、変更の無い先頭と末尾は表に入れない
+  let old: String = (0..100000).map(|i| format!("{}\n", i)).collect();
+  let new = old.replacen("50000\n", "fifty thousand\n", 1);
+  assert!(make_diff_str("a", &old, "b", &new)
+    .ends_with("@@ -49998,7 +49998,7 @@\n 49997\n 49998\n 49999\n-50000\n+fifty thousand\n 50001\n 50002\n 50003\n"));
+  // 表が大きすぎるときは、間を全て削除して全て追加する
+  let new: String = (0..3000).map(|i| format!("new {}\n", i)).collect();
+  let diff_str = make_diff_str("a", &old[..old.find("3000\n").unwrap()], "b", &new);
+  assert!(diff_str.starts_with("--- a\n+++ b\n@@ -1,3000 +1,3000 @@\n-0\n"));
+  assert!(diff_str.ends_with("+new 2999\n"));
+}
@@ -1,5 +1,3 @@
-use std::fs::File;
-use std::io::prelude::*;
 use std::process;
 use std::str::from_utf8;
 
@@ -13,43 +11,163 @@ pub enum OptionError {
   NotFoundInputFileName(String),
   BrokenInputFile(String),
   BrokenInputFilePath(String),
+  NotFoundInputDirectory(String),
+  NoOutputDirectory,
+  BrokenOutputFile(String),
+  CannotWatchStdin,
+  CannotReplStdin,
+  NotAllowedWithMultipleInputs(String),
+  OnlyWithMultipleInputs(String),
+  DuplicateOutputFile(String, String, String),
+  InvalidNumber(String, String),
+  NotAllowedWithEmit(String, String),
 }
 
 #[derive(Debug, Clone)]
 pub enum ConfigError {
   NotFoundPubFunctin,
-  NotFoundTokenTypeStr(String),
-  NotFoundFunctionName(String),
+  NotFoundTokenTypeStr(types::Range, String, Option<String>),
+  NotFoundFunctionName(types::Range, String, Option<String>),
+  InvalidTypeStr(types::Range, String),
+  InvalidTokenPattern(types::Range, String),
+  InvalidCode(types::Range, String),
+  LeftRecursion(types::Range, Vec<String>),
+  Conflict(types::Range, String, (usize, usize), Option<String>),
+  DuplicateTypeName(types::Range, String, String, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum GrammarError {
+  MissingLabel(types::Range, String, usize),
+  DuplicateLabel(types::Range, String, String),
+  ReservedRuleName(types::Range, String),
+  ReservedTypeName(types::Range, String, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum VerifyError {
+  NotFoundOutputFile(String),
+  StaleOutputFile(String, String),
+  UnformattedFile(String, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum RenameError {
+  NotFoundName(String),
+  InvalidRuleName(String),
+  InvalidTokenName(String),
+  AlreadyDefinedName(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum TestError {
+  InvalidTestCase(usize, String),
+  NotFoundTestRule(usize, String),
+  NotFoundTestToken(usize, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplError {
+  NotFoundRule(String),
+  NotFoundToken(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum SampleError {
+  NotFoundRule(String),
+  NoFiniteDerivation(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum CoverageError {
+  InvalidCoverageLine(usize, String),
+  NotFoundAlternative(String, usize),
 }
 
 #[derive(Debug, Clone)]
 pub enum Error {
   OptionError(OptionError),
+  VerifyError(VerifyError),
   LexerError(lexer::LexError),
   ParserError(parse::ParseError),
   ConfigError(ConfigError),
+  GrammarError(GrammarError),
+  RenameError(RenameError),
+  TestError(TestError),
+  ReplError(ReplError),
+  SampleError(SampleError),
+  CoverageError(CoverageError),
 }
 
-pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
+pub fn make_error_msg(
+  err: Error,
+  input_file_name_opt: Option<&str>,
+  input_opt: Option<&str>,
+) -> String {
   match err {
     Error::OptionError(e) => match e {
-      OptionError::NoInputFileName => {
-        eprintln! {"![option error]\n  no input file name"}
-      }
+      OptionError::NoInputFileName => "![option error]\n  no input file name".to_string(),
       OptionError::NotFoundInputFileName(s) => {
-        eprintln!("![opiton error]\n  not found input file: {}", s)
+        format!("![opiton error]\n  not found input file: {}", s)
       }
-      OptionError::BrokenInputFile(s) => eprintln!("![opiton error]\n  broken input file: {}", s),
+      OptionError::BrokenInputFile(s) => format!("![opiton error]\n  broken input file: {}", s),
       OptionError::BrokenInputFilePath(s) => {
-        eprintln!("![opiton error]\n  broken input file path: {}", s)
+        format!("![opiton error]\n  broken input file path: {}", s)
+      }
+      OptionError::NotFoundInputDirectory(s) => {
+        format!("![opiton error]\n  not found input directory: {}", s)
+      }
+      OptionError::NoOutputDirectory => {
+        "![option error]\n  no output directory (OUT_DIR is not set)".to_string()
+      }
+      OptionError::BrokenOutputFile(s) => {
+        format!("![opiton error]\n  cannot write output file: {}", s)
+      }
+      OptionError::CannotWatchStdin => {
+        "![option error]\n  cannot watch stdin; specify an input file".to_string()
+      }
+      OptionError::CannotReplStdin => {
+        "![option error]\n  cannot read a grammar from stdin in the repl; specify an input file"
+          .to_string()
+      }
+      OptionError::NotAllowedWithMultipleInputs(s) => format!(
+        "![option error]\n  {} cannot be used with multiple input files or --out-dir",
+        s
+      ),
+      OptionError::OnlyWithMultipleInputs(s) => format!(
+        "![option error]\n  {} can only be used with multiple input files or --out-dir",
+        s
+      ),
+      OptionError::DuplicateOutputFile(input1, input2, output) => format!(
+        "![option error]\n  '{}' and '{}' would both be written to '{}'",
+        input1, input2, output
+      ),
+      OptionError::NotAllowedWithEmit(name, emit) => format!(
+        "![option error]\n  {} cannot be used with --emit={}",
+        name, emit
+      ),
+      OptionError::InvalidNumber(name, s) => format!(
+        "![option error]\n  invalid value '{}' for {}: expected a positive number",
+        s, name
+      ),
+    },
+    Error::VerifyError(e) => match e {
+      VerifyError::NotFoundOutputFile(s) => {
+        format!("![verify error]\n  not found output file: {}", s)
+      }
+      VerifyError::StaleOutputFile(s, diff_str) => format!(
+        "![verify error]\n  '{}' is not up to date with '{}'\n{}",
+        s,
+        input_file_name_opt.unwrap(),
+        diff_str
+      ),
+      VerifyError::UnformattedFile(s, diff_str) => {
+        format!("![verify error]\n  '{}' is not formatted\n{}", s, diff_str)
       }
     },
     Error::LexerError(e) => {
-      // OptionErrorではないので、ファイルを読みこむことができることは保障されている。
-      let mut f = File::open(&mut input_file_name_opt.unwrap()).unwrap();
-      let mut contents = String::new();
-      f.read_to_string(&mut contents).unwrap();
-      let input_bytes = contents.as_bytes();
+      // OptionErrorではないので、入力を読みこめていることは保障されている。
+      let input_bytes = input_opt.unwrap().as_bytes();
       let (e, rng) = e;
       match e {
         lexer::LexErrorKind::InvalidChar(c) => {
@@ -57,7 +175,7 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
           let input_file_path = input_file_name_opt.unwrap();
           let (start_row, start_column) = start_pos;
           let start_pos_s = format!("{}:{}", start_row, start_column);
-          eprintln!(
+          format!(
             "![lexer error]\n  illegal token '{}' at {}:{}\n{}",
             c, input_file_path, start_pos_s, err_point_s
           )
@@ -67,7 +185,7 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
           let input_file_path = input_file_name_opt.unwrap();
           let (start_row, start_column) = start_pos;
           let start_pos_s = format!("{}:{}", start_row, start_column);
-          eprintln!(
+          format!(
             "![lexer error]\n  undefinde token \"{}\" at {}:{}\n{}",
             s, input_file_path, start_pos_s, err_point_s
           )
@@ -77,7 +195,7 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
           let input_file_path = input_file_name_opt.unwrap();
           let (start_row, start_column) = start_pos;
           let start_pos_s = format!("{}:{}", start_row, start_column);
-          eprintln!(
+          format!(
             "![lexer error]\n unexpected end of file at {}:{}",
             input_file_path, start_pos_s
           )
@@ -85,11 +203,8 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
       }
     }
     Error::ParserError(e) => {
-      // OptionErrorではないので、ファイルを読みこむことができることは保障されている。
-      let mut f = File::open(&mut input_file_name_opt.unwrap()).unwrap();
-      let mut contents = String::new();
-      f.read_to_string(&mut contents).unwrap();
-      let input_bytes = contents.as_bytes();
+      // OptionErrorではないので、入力を読みこめていることは保障されている。
+      let input_bytes = input_opt.unwrap().as_bytes();
       match e {
         parse::ParseError::UnexpectedToken(tok) => {
           let (_errkind, rng) = tok;
@@ -97,7 +212,7 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
           let input_file_path = input_file_name_opt.unwrap();
           let (start_row, start_column) = start_pos;
           let start_pos_s = format!("{}:{}", start_row, start_column);
-          eprintln!(
+          format!(
             "![parse error]\n  unexpected token '{}' at {}:{}",
             err_point_s, input_file_path, start_pos_s
           )
@@ -108,52 +223,376 @@ pub fn print_error_msg(err: Error, input_file_name_opt: Option<&str>) {
           let input_file_path = input_file_name_opt.unwrap();
           let (start_row, start_column) = start_pos;
           let start_pos_s = format!("{}:{}", start_row, start_column);
-          eprintln!(
+          format!(
             "![parse error]\n  redundant expression '{}' at {}:{}",
             err_point_s, input_file_path, start_pos_s
           )
         }
         parse::ParseError::Eof => {
           let input_file_path = input_file_name_opt.unwrap();
-          eprintln!(
+          format!(
             "![parse error]\n  unexpected end of file at {}",
             input_file_path
           )
         }
       }
     }
-    Error::ConfigError(e) => match e {
-      ConfigError::NotFoundPubFunctin => {
-        let input_file_path = input_file_name_opt.unwrap();
-        eprintln!(
-          "![config file error]\n not found pub function at {}",
-          input_file_path
-        )
-      }
-      ConfigError::NotFoundTokenTypeStr(s) => {
-        let input_file_path = input_file_name_opt.unwrap();
-        eprintln!(
-          "![config file error]\n not found \"{}\"'s type at {}",
-          s, input_file_path
-        )
+    Error::ConfigError(e) => {
+      // OptionErrorではないので、入力を読みこめていることは保障されている。
+      let input_bytes = input_opt.unwrap().as_bytes();
+      match e {
+        ConfigError::NotFoundPubFunctin => {
+          let input_file_path = input_file_name_opt.unwrap();
+          format!(
+            "![config file error]\n not found pub function at {}",
+            input_file_path
+          )
+        }
+        ConfigError::NotFoundTokenTypeStr(rng, s, suggestion_opt) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  not found token \"{}\" at {}:{}\n{}{}",
+            s,
+            input_file_path,
+            start_pos_s,
+            err_point_s,
+            suggestion_to_str(suggestion_opt)
+          )
+        }
+        ConfigError::NotFoundFunctionName(rng, s, suggestion_opt) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  not found function \"{}\" at {}:{}\n{}{}",
+            s,
+            input_file_path,
+            start_pos_s,
+            err_point_s,
+            suggestion_to_str(suggestion_opt)
+          )
+        }
+        ConfigError::InvalidTypeStr(rng, msg) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  invalid type {} at {}:{}\n  {}",
+            err_point_s, input_file_path, start_pos_s, msg
+          )
+        }
+        ConfigError::InvalidTokenPattern(rng, msg) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  invalid token pattern {} at {}:{}\n  {}",
+            err_point_s, input_file_path, start_pos_s, msg
+          )
+        }
+        ConfigError::InvalidCode(rng, msg) => {
+          let (_, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  invalid code at {}:{}\n  {}",
+            input_file_path, start_pos_s, msg
+          )
+        }
+        ConfigError::LeftRecursion(rng, path) => {
+          let (_, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  left recursion in \"{}\" at {}:{}\n  {}",
+            path[0],
+            input_file_path,
+            start_pos_s,
+            path.join(" -> ")
+          )
+        }
+        ConfigError::Conflict(rng, name, (i, j), token_opt) => {
+          let (_, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          let reason = match token_opt {
+            Some(tok_name) => format!("are both selected by `{}`", tok_name),
+            None => "are both empty".to_string(),
+          };
+          format!(
+            "![config file error]\n  conflict in \"{}\" at {}:{}\n  alternatives #{} and #{} {}",
+            name,
+            input_file_path,
+            start_pos_s,
+            i + 1,
+            j + 1,
+            reason
+          )
+        }
+        ConfigError::DuplicateTypeName(rng, name, other_name, type_name) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![config file error]\n  rules \"{}\" and \"{}\" are both named `{}` in the generated code at {}:{}\n{}",
+            other_name, name, type_name, input_file_path, start_pos_s, err_point_s
+          )
+        }
       }
-      ConfigError::NotFoundFunctionName(s) => {
-        let input_file_path = input_file_name_opt.unwrap();
-        eprintln!(
-          "![config file error]\n not found \"{}\"'s name at {}",
-          s, input_file_path
-        )
+    }
+    Error::GrammarError(e) => {
+      // OptionErrorではないので、入力を読みこめていることは保障されている。
+      let input_bytes = input_opt.unwrap().as_bytes();
+      match e {
+        GrammarError::MissingLabel(rng, name, i) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![grammar error]\n  alternative #{} of \"{}\" needs a label (`=> Label`) at {}:{}\n{}",
+            i + 1,
+            name,
+            input_file_path,
+            start_pos_s,
+            err_point_s
+          )
+        }
+        GrammarError::DuplicateLabel(rng, name, label) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![grammar error]\n  label `{}` is used more than once in \"{}\" at {}:{}\n{}",
+            label, name, input_file_path, start_pos_s, err_point_s
+          )
+        }
+        GrammarError::ReservedRuleName(rng, name) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![grammar error]\n  rule \"{}\" needs a type because `visit_{}` and `fold_{}` are used for tokens at {}:{}\n{}",
+            name, name, name, input_file_path, start_pos_s, err_point_s
+          )
+        }
+        GrammarError::ReservedTypeName(rng, name, type_name) => {
+          let (err_point_s, start_pos, _end_pos) = get_error_point(rng, input_bytes);
+          let input_file_path = input_file_name_opt.unwrap();
+          let (start_row, start_column) = start_pos;
+          let start_pos_s = format!("{}:{}", start_row, start_column);
+          format!(
+            "![grammar error]\n  rule \"{}\" would generate `{}`, which the generated code already uses at {}:{}\n{}",
+            name, type_name, input_file_path, start_pos_s, err_point_s
+          )
+        }
       }
+    }
+    Error::RenameError(e) => match e {
+      RenameError::NotFoundName(s) => format!(
+        "![rename error]\n  not found rule or token \"{}\" in {}",
+        s,
+        input_file_name_opt.unwrap()
+      ),
+      RenameError::InvalidRuleName(s) => format!(
+        "![rename error]\n  invalid rule name \"{}\"\n  a rule name starts with a lowercase letter or '_'",
+        s
+      ),
+      RenameError::InvalidTokenName(s) => format!(
+        "![rename error]\n  invalid token name \"{}\"\n  a token name starts with an uppercase letter",
+        s
+      ),
+      RenameError::AlreadyDefinedName(s) => format!(
+        "![rename error]\n  \"{}\" is already defined in {}",
+        s,
+        input_file_name_opt.unwrap()
+      ),
+    },
+    Error::TestError(e) => match e {
+      TestError::InvalidTestCase(line, s) => format!(
+        "![test error]\n  invalid test at {}:{}\n  {}\n  a test is written as `test <rule> accepts \"<tokens>\"` or `test <rule> rejects \"<tokens>\"`",
+        input_file_name_opt.unwrap(),
+        line,
+        s
+      ),
+      TestError::NotFoundTestRule(line, s) => format!(
+        "![test error]\n  not found rule \"{}\" at {}:{}",
+        s,
+        input_file_name_opt.unwrap(),
+        line
+      ),
+      TestError::NotFoundTestToken(line, s) => format!(
+        "![test error]\n  not found token \"{}\" at {}:{}",
+        s,
+        input_file_name_opt.unwrap(),
+        line
+      ),
+    },
+    Error::ReplError(e) => match e {
+      ReplError::NotFoundRule(s) => format!(
+        "![repl error]\n  not found rule \"{}\" in {}",
+        s,
+        input_file_name_opt.unwrap()
+      ),
+      ReplError::NotFoundToken(s) => format!(
+        "![repl error]\n  not found token \"{}\" in {}",
+        s,
+        input_file_name_opt.unwrap()
+      ),
+    },
+    Error::SampleError(e) => match e {
+      SampleError::NotFoundRule(s) => format!(
+        "![sample error]\n  not found rule \"{}\" in {}",
+        s,
+        input_file_name_opt.unwrap()
+      ),
+      SampleError::NoFiniteDerivation(s) => format!(
+        "![sample error]\n  rule \"{}\" cannot derive a finite sequence of tokens",
+        s
+      ),
     },
-  };
+    Error::CoverageError(e) => match e {
+      CoverageError::InvalidCoverageLine(line, s) => format!(
+        "![coverage error]\n  invalid line at {}:{}\n  {}\n  each line is written as `<rule> <alternative index> <count>`",
+        input_file_name_opt.unwrap(),
+        line,
+        s
+      ),
+      CoverageError::NotFoundAlternative(s, i) => format!(
+        "![coverage error]\n  not found alternative #{} of rule \"{}\" in {}\n  the counts may have been recorded with an older version of the grammar",
+        i,
+        s,
+        input_file_name_opt.unwrap()
+      ),
+    },
+  }
+}
+
+pub fn print_error_msg(
+  err: Error,
+  input_file_name_opt: Option<&str>,
+  input_opt: Option<&str>,
+) -> ! {
+  eprintln!("{}", make_error_msg(err, input_file_name_opt, input_opt));
   process::exit(1);
 }
 
-fn get_error_point(
+// 処理は続けるが、文法に問題があることを表示する
+pub fn print_warning_msg(err: Error, input_file_name_opt: Option<&str>, input_opt: Option<&str>) {
+  let msg = make_error_msg(err, input_file_name_opt, input_opt);
+  eprintln!("{}", msg.replacen(" error]", " warning]", 1));
+}
+
+fn suggestion_to_str(suggestion_opt: Option<String>) -> String {
+  match suggestion_opt {
+    Some(s) => format!("\n  did you mean `{}`?", s),
+    None => String::new(),
+  }
+}
+
+// エラーの起きた文法ファイル上の位置
+pub fn get_error_range(err: &Error) -> Option<types::Range> {
+  match err {
+    Error::OptionError(_) => None,
+    Error::VerifyError(_) => None,
+    Error::RenameError(_) => None,
+    Error::TestError(_) => None,
+    Error::ReplError(_) => None,
+    Error::SampleError(_) => None,
+    Error::CoverageError(_) => None,
+    Error::LexerError((_, rng)) => Some(*rng),
+    Error::ParserError(e) => match e {
+      parse::ParseError::UnexpectedToken((_, rng)) => Some(*rng),
+      parse::ParseError::RedundantExpression((_, rng)) => Some(*rng),
+      parse::ParseError::Eof => None,
+    },
+    Error::ConfigError(e) => match e {
+      ConfigError::NotFoundPubFunctin => None,
+      ConfigError::NotFoundTokenTypeStr(rng, _, _) => Some(*rng),
+      ConfigError::NotFoundFunctionName(rng, _, _) => Some(*rng),
+      ConfigError::InvalidTypeStr(rng, _) => Some(*rng),
+      ConfigError::InvalidTokenPattern(rng, _) => Some(*rng),
+      ConfigError::InvalidCode(rng, _) => Some(*rng),
+      ConfigError::LeftRecursion(rng, _) => Some(*rng),
+      ConfigError::Conflict(rng, _, _, _) => Some(*rng),
+      ConfigError::DuplicateTypeName(rng, _, _, _) => Some(*rng),
+    },
+    Error::GrammarError(e) => match e {
+      GrammarError::MissingLabel(rng, _, _) => Some(*rng),
+      GrammarError::DuplicateLabel(rng, _, _) => Some(*rng),
+      GrammarError::ReservedRuleName(rng, _) => Some(*rng),
+      GrammarError::ReservedTypeName(rng, _, _) => Some(*rng),
+    },
+  }
+}
+
+// 編集距離が十分に近い名前のうち、最も近いものを返す
+pub fn find_similar_name(name: &str, candidates: &[&String]) -> Option<String> {
+  let max_dist = std::cmp::max(name.len(), 3) / 3;
+  let mut best: Option<(usize, &String)> = None;
+  for candidate in candidates.iter() {
+    let dist = edit_distance(name, candidate);
+    if dist > max_dist {
+      continue;
+    }
+    match best {
+      Some((best_dist, best_name)) if (best_dist, best_name) <= (dist, candidate) => (),
+      _ => best = Some((dist, candidate)),
+    }
+  }
+  best.map(|(_, s)| s.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+  let mut prev: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.iter().enumerate() {
+    let mut cur = vec![i + 1];
+    for (j, cb) in b.iter().enumerate() {
+      let cost = if ca == cb { 0 } else { 1 };
+      let v = std::cmp::min(std::cmp::min(prev[j + 1] + 1, cur[j] + 1), prev[j] + cost);
+      cur.push(v);
+    }
+    prev = cur;
+  }
+  prev[b.len()]
+}
+
+#[test]
+fn check_find_similar_name() {
+  let tok_comma = "Tok_COMMA".to_string();
+  let tok_colon = "Tok_COLON".to_string();
+  let tok_str = "Tok_STR".to_string();
+  let candidates = vec![&tok_comma, &tok_colon, &tok_str];
+  assert_eq!(
+    find_similar_name("Tok_COMA", &candidates),
+    Some("Tok_COMMA".to_string())
+  );
+  assert_eq!(find_similar_name("Tok_EOF", &candidates), None);
+  assert_eq!(edit_distance("kitten", "sitting"), 3);
+}
+
+pub fn get_error_point(
   range: types::Range,
   input_bytes: &[u8],
 ) -> (String, (usize, usize), (usize, usize)) {
   let (start, end) = range.to_tuple();
+  // EOFの位置情報は入力の末尾を超えるので、入力の範囲に収める
+  let start = start.min(input_bytes.len());
+  let end = end.min(input_bytes.len()).max(start);
   let error_point_string = from_utf8(&input_bytes[start..end]).unwrap().to_string();
   let mut start_pos = (1, 0);
   let mut end_pos = (1, 0);
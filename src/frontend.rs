@@ -1,6 +1,7 @@
 use super::error;
 use super::types;
 
+pub mod fmt;
 pub mod lexer;
 pub mod parse;
 
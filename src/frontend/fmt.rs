@@ -0,0 +1,489 @@
+use super::error;
+use super::lexer::{self, TokenKind};
+
+// 文法ファイルを決まった形に整える
+// 字句解析器はコメントを読み飛ばすので、トークンの間の文字列からコメントを拾い直し、
+// トークンの並びを決まった改行と字下げで出力する
+// 文字列（型、パターン、アクションのコード）は中身に手を付けずにそのまま出力する
+
+// これより長くなる行は、記号の並びの途中で折り返す
+const MAX_WIDTH: usize = 100;
+
+// トークンの前にある、それだけで1行を占めるコメントと空行
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+  Comment(String),
+  Blank,
+}
+
+struct Formatter<'a> {
+  input: &'a str,
+  tokens: Vec<lexer::Token>,
+  pos: usize,
+  // トークンごとの、その前にあるコメント
+  leading_lst: Vec<Vec<Line>>,
+  // トークンごとの、同じ行でその後にあるコメント
+  trailing_lst: Vec<Option<String>>,
+  out: String,
+  line: String,
+  // 行の途中のトークンの前で改行したときの字下げ
+  cont_indent: usize,
+  // 今の行の最後に付けるコメント
+  pending_comment: Option<String>,
+  // begin_lineで行を始めたか（始めていなければ、行の途中で改行したことになる）
+  is_line_begun: bool,
+}
+
+// トークンの間の文字列（空白とコメントしかない）からコメントを拾う
+fn collect_comments(input: &str, tokens: &[lexer::Token]) -> (Vec<Vec<Line>>, Vec<Option<String>>) {
+  let bytes = input.as_bytes();
+  let mut leading_lst = Vec::new();
+  let mut trailing_lst = vec![None; tokens.len()];
+  let mut prev_end = 0;
+  for (i, (_, rng)) in tokens.iter().enumerate() {
+    let (start, end) = rng.to_tuple();
+    let start = start.min(bytes.len());
+    let mut leading = Vec::new();
+    let mut newline_count = 0;
+    let mut pos = prev_end;
+    while pos < start {
+      if bytes[pos] == b'/' {
+        let comment_end = input[pos..start]
+          .find('\n')
+          .map_or(start, |offset| pos + offset);
+        let comment = input[pos..comment_end].trim_end().to_string();
+        if i > 0 && newline_count == 0 && leading.is_empty() {
+          trailing_lst[i - 1] = Some(comment)
+        } else {
+          if newline_count >= 2 && !leading.is_empty() {
+            leading.push(Line::Blank)
+          }
+          leading.push(Line::Comment(comment))
+        }
+        newline_count = 0;
+        pos = comment_end;
+      } else {
+        if bytes[pos] == b'\n' {
+          newline_count += 1
+        }
+        pos += 1;
+      }
+    }
+    if newline_count >= 2 && !leading.is_empty() {
+      leading.push(Line::Blank)
+    }
+    leading_lst.push(leading);
+    prev_end = end;
+  }
+  (leading_lst, trailing_lst)
+}
+
+impl<'a> Formatter<'a> {
+  fn kind(&self) -> &TokenKind {
+    &self.tokens[self.pos].0
+  }
+
+  fn text(&self, pos: usize) -> &'a str {
+    let (start, end) = self.tokens[pos].1.to_tuple();
+    &self.input[start..end]
+  }
+
+  // 今の行の、最後の改行より後の幅
+  fn line_width(&self) -> usize {
+    match self.line.rfind('\n') {
+      Some(i) => self.line[i + 1..].chars().count(),
+      None => self.line.chars().count(),
+    }
+  }
+
+  fn is_line_empty(&self) -> bool {
+    self.line.trim().is_empty()
+  }
+
+  fn flush_line(&mut self) {
+    if let Some(comment) = self.pending_comment.take() {
+      self.line.push(' ');
+      self.line.push_str(&comment);
+    }
+    if !self.is_line_empty() {
+      self.out.push_str(self.line.trim_end());
+      self.out.push('\n');
+    }
+    self.line.clear();
+    self.is_line_begun = false;
+  }
+
+  fn push_blank_line(&mut self) {
+    self.flush_line();
+    if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+      self.out.push('\n')
+    }
+  }
+
+  // 今のトークンの前にあるコメントを、字下げしてそれぞれ1行で出力する
+  fn write_leading_comments(&mut self, indent: usize) {
+    let leading = std::mem::take(&mut self.leading_lst[self.pos]);
+    for line in leading.into_iter() {
+      match line {
+        Line::Comment(comment) => {
+          self.out.push_str(&" ".repeat(indent));
+          self.out.push_str(&comment);
+          self.out.push('\n');
+        }
+        Line::Blank => self.push_blank_line(),
+      }
+    }
+  }
+
+  // 次のトークンを新しい行の先頭に置く
+  fn begin_line(&mut self, indent: usize) {
+    self.flush_line();
+    self.write_leading_comments(indent);
+    self.line = " ".repeat(indent);
+    self.is_line_begun = true;
+  }
+
+  // 次のトークンを今の行にsepで区切って続ける
+  // 前にコメントがあるときは、コメントと一緒に次の行に移す
+  fn token(&mut self, sep: &str) {
+    if self.pending_comment.is_some() || !self.leading_lst[self.pos].is_empty() {
+      self.flush_line();
+      self.write_leading_comments(self.cont_indent);
+    }
+    if self.is_line_empty() {
+      if !self.is_line_begun {
+        self.line = " ".repeat(self.cont_indent);
+        self.is_line_begun = true;
+      }
+    } else {
+      self.line.push_str(sep)
+    }
+    let text = self.text(self.pos);
+    self.line.push_str(text);
+    self.pending_comment = self.trailing_lst[self.pos].take();
+    self.pos += 1;
+  }
+
+  // 省略できる区切り記号は、省略されていても出力する
+  fn optional_token(&mut self, kind: TokenKind, text: &str) {
+    if self.kind() == &kind {
+      self.token("")
+    } else {
+      self.line.push_str(text)
+    }
+  }
+
+  fn format_file(&mut self) {
+    // 先頭の文字列
+    while let TokenKind::STR(_) = self.kind() {
+      self.begin_line(0);
+      self.token("");
+    }
+    // grammar;
+    self.push_blank_line();
+    self.begin_line(0);
+    self.token("");
+    self.token("");
+    self.push_blank_line();
+    self.format_extern();
+    while self.kind() != &TokenKind::EOF {
+      self.push_blank_line();
+      self.format_rule();
+    }
+    if !self.leading_lst[self.pos].is_empty() {
+      self.push_blank_line();
+      self.write_leading_comments(0);
+    }
+    self.flush_line();
+  }
+
+  fn format_extern(&mut self) {
+    self.cont_indent = 2;
+    // extern {
+    self.begin_line(0);
+    self.token("");
+    self.token(" ");
+    // enum "Type" {
+    self.cont_indent = 4;
+    self.begin_line(2);
+    self.token("");
+    self.token(" ");
+    self.token(" ");
+    // 矢印の位置を揃える
+    let width = self.tokens[self.pos..]
+      .iter()
+      .take_while(|(kind, _)| kind != &TokenKind::RCURLYBRACES)
+      .filter_map(|(kind, _)| match kind {
+        TokenKind::CONSTRUCTOR(name) => Some(name.chars().count()),
+        _ => None,
+      })
+      .max()
+      .unwrap_or(0);
+    self.cont_indent = 6;
+    while let TokenKind::CONSTRUCTOR(name) = self.kind() {
+      let padding = " ".repeat(width - name.chars().count() + 1);
+      self.begin_line(4);
+      self.token("");
+      self.token(&padding);
+      self.token(" ");
+      self.optional_token(TokenKind::COMMA, ",");
+    }
+    self.cont_indent = 4;
+    self.begin_line(2);
+    self.token("");
+    self.cont_indent = 2;
+    self.begin_line(0);
+    self.token("");
+  }
+
+  fn format_rule(&mut self) {
+    self.cont_indent = 2;
+    // [pub] name: "Type" = {
+    // 型を省略した規則は [pub] name = {
+    self.begin_line(0);
+    if self.kind() == &TokenKind::PUB {
+      self.token("");
+    }
+    self.token(" ");
+    if self.kind() == &TokenKind::COLON {
+      self.token("");
+      self.token(" ");
+    }
+    self.token(" ");
+    self.token(" ");
+    while self.kind() != &TokenKind::RCURLYBRACES {
+      self.format_alternative();
+    }
+    self.cont_indent = 2;
+    self.begin_line(0);
+    self.token("");
+    self.optional_token(TokenKind::SEMICOLON, ";");
+  }
+
+  // 入力の中で、トークンがその行の先頭（字下げの後）にあれば、その桁を返す
+  fn line_start_column(&self, pos: usize) -> Option<usize> {
+    let (start, _) = self.tokens[pos].1.to_tuple();
+    let line_start = self.input[..start].rfind('\n').map_or(0, |i| i + 1);
+    let before = &self.input[line_start..start];
+    if before.trim().is_empty() {
+      Some(before.chars().count())
+    } else {
+      None
+    }
+  }
+
+  // 選択肢の最後の記号の後に、同じ行に続く部分（` => {`、` => Label,`、`,`など）の幅
+  // 1行のコードは、続けると長すぎるときは次の行に置かれるので含めない
+  fn alternative_rest_width(&self, pos: usize) -> usize {
+    match &self.tokens[pos].0 {
+      TokenKind::ARROW => match &self.tokens[pos + 1].0 {
+        TokenKind::CONSTRUCTOR(name) => " => ".len() + name.chars().count() + 1,
+        _ => {
+          let code = self.text(pos + 2);
+          let code_width = if code.contains('\n') && self.line_start_column(pos + 2).is_none() {
+            code.split('\n').next().unwrap_or("").chars().count()
+          } else {
+            0
+          };
+          " => {".len() + code_width
+        }
+      },
+      _ => 1,
+    }
+  }
+
+  fn format_alternative(&mut self) {
+    self.cont_indent = 4;
+    self.begin_line(2);
+    // <name: rule_or_token> を並べ、長ければ折り返す
+    // 最後の記号は、その後に同じ行に続く部分も含めて幅を測る
+    while self.kind() == &TokenKind::LBRACES {
+      let mut symbol_width: usize = (self.pos..self.pos + 5)
+        .map(|pos| self.text(pos).chars().count())
+        .sum::<usize>()
+        + 1;
+      if self.tokens[self.pos + 5].0 != TokenKind::LBRACES {
+        symbol_width += self.alternative_rest_width(self.pos + 5);
+      }
+      if !self.is_line_empty() && self.line_width() + 1 + symbol_width > MAX_WIDTH {
+        self.flush_line();
+      }
+      self.token(" ");
+      self.token("");
+      self.token("");
+      self.token(" ");
+      self.token("");
+    }
+    // 型を省略した規則の選択肢は、アクションが無いか => Label
+    if self.kind() != &TokenKind::ARROW {
+      self.optional_token(TokenKind::COMMA, ",");
+      return;
+    }
+    self.token(" ");
+    if let TokenKind::CONSTRUCTOR(_) = self.kind() {
+      self.token(" ");
+      self.optional_token(TokenKind::COMMA, ",");
+      return;
+    }
+    // => {"code"}
+    self.token(" ");
+    // 続けると長すぎるコードは次の行に置く
+    // 複数行のコードは、入力で行の先頭にあればその桁のまま次の行に置き、そうでなければ続ける
+    let code = self.text(self.pos);
+    // 2行目以降はそのまま出力するので、1行目の桁を変えると字下げがずれる
+    let column_opt = if code.contains('\n') {
+      self.line_start_column(self.pos)
+    } else if self.line_width() + code.chars().count() + 2 > MAX_WIDTH {
+      Some(4)
+    } else {
+      None
+    };
+    match column_opt {
+      Some(column) => {
+        self.begin_line(column);
+        self.token("");
+        self.begin_line(2);
+      }
+      None => self.token(""),
+    }
+    self.token("");
+    self.optional_token(TokenKind::COMMA, ",");
+  }
+}
+
+/// 文法ファイルの中身を決まった形に整えたものを返す
+///
+/// コメントは残し、字下げ、空白、空行、`extern`のトークンの`=>`の位置を揃える。
+/// 文字列の中身には手を付けない。文法ファイルとして読み込めなければエラーを返す。
+pub fn format_str(input: &str) -> Result<String, error::Error> {
+  let _ = super::get_ast(input)?;
+  let tokens = match lexer::lex(input) {
+    Ok(t) => Ok(t),
+    Err(e) => Err(error::Error::LexerError(e)),
+  }?;
+  let (leading_lst, trailing_lst) = collect_comments(input, &tokens);
+  let mut formatter = Formatter {
+    input,
+    tokens,
+    pos: 0,
+    leading_lst,
+    trailing_lst,
+    out: String::new(),
+    line: String::new(),
+    cont_indent: 0,
+    pending_comment: None,
+    is_line_begun: false,
+  };
+  formatter.format_file();
+  Ok(formatter.out)
+}
+
+#[test]
+fn check_format_str() {
+  let input = "// head
+\"use super::lexer;\"
+grammar;
+extern {
+  enum \"lexer::Token\" {
+    Tok_A => \"'a'\", // a
+    Tok_LONG=>\"'long'\"
+  }
+}
+
+
+pub main:\"()\"={
+  // first
+  <_a:Tok_A>  <_b: sub>=>{\"()\"},
+  => {
+    \"()
+    \"
+  }
+};
+sub: \"()\" = {<_l: Tok_LONG> => {\"()\"}};
+auto={<a:Tok_A>=>A,=>B}
+// end
+";
+  let expected = "// head
+\"use super::lexer;\"
+
+grammar;
+
+extern {
+  enum \"lexer::Token\" {
+    Tok_A    => \"'a'\", // a
+    Tok_LONG => \"'long'\",
+  }
+}
+
+pub main: \"()\" = {
+  // first
+  <_a: Tok_A> <_b: sub> => {\"()\"},
+  => {
+    \"()
+    \"
+  },
+};
+
+sub: \"()\" = {
+  <_l: Tok_LONG> => {\"()\"},
+};
+
+auto = {
+  <a: Tok_A> => A,
+  => B,
+};
+
+// end
+";
+  assert_eq!(format_str(input).unwrap(), expected);
+  assert_eq!(format_str(expected).unwrap(), expected);
+
+  // 行幅には ` => {` も含め、複数行のアクションは元の列を保つ
+  let input = "\"\"
+grammar;
+extern {
+  enum \"char\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <a_long_label_name: Tok_A> <another_long_label: Tok_A> <yet_another_label: Tok_A> <x: Tok_A> => {\"()\"},
+  <_a: Tok_A> => {
+      \"let v = ();
+      v\"
+  },
+};
+";
+  let expected = "\"\"
+
+grammar;
+
+extern {
+  enum \"char\" {
+    Tok_A => \"'a'\",
+  }
+}
+
+pub main: \"()\" = {
+  <a_long_label_name: Tok_A> <another_long_label: Tok_A> <yet_another_label: Tok_A> <x: Tok_A> => {
+    \"()\"
+  },
+  <_a: Tok_A> => {
+      \"let v = ();
+      v\"
+  },
+};
+";
+  assert_eq!(format_str(input).unwrap(), expected);
+  assert_eq!(format_str(expected).unwrap(), expected);
+
+  // このリポジトリの文法ファイルも整形済み
+  for input in [
+    include_str!("parse.mkr"),
+    include_str!("../../demo/demo.mkr"),
+  ]
+  .iter()
+  {
+    assert_eq!(&format_str(input).unwrap(), input);
+  }
+}
@@ -327,14 +327,13 @@ fn lex_literal(input: &[u8], pos: usize) -> Result<(Token, usize), LexError> {
             s_pos += 1;
             v.push(*b)
           }
-          Some(b_next) => {
-            if b_next == &b'"' {
-              s_pos += 2;
-              v.push(b'"')
-            } else {
-              s_pos += 1;
-              v.push(*b)
-            }
+          Some(b'"') => {
+            s_pos += 2;
+            v.push(b'"')
+          }
+          Some(_) => {
+            s_pos += 1;
+            v.push(*b)
           }
         },
         b'"' => {
@@ -403,7 +402,7 @@ fn lex_rcurlybraces(pos: usize) -> (Token, usize) {
 }
 
 fn lex_eq(pos: usize) -> (Token, usize) {
-  ((TokenKind::EQ, types::Range::make(pos, 2)), pos + 2)
+  ((TokenKind::EQ, types::Range::make(pos, 1)), pos + 1)
 }
 
 fn lex_comma(pos: usize) -> (Token, usize) {
@@ -2,9 +2,10 @@
 // This file was generated by llmaker.
 //
 
+use std::cmp::Ordering;
+
 use super::lexer;
 use super::types;
-use std::cmp::Ordering;
 
 #[derive(Debug, Clone)]
 pub enum ParseError {
@@ -25,10 +26,13 @@ pub fn parse(tokens: Vec<lexer::Token>) -> Result<types::Term, ParseError> {
   }
 }
 
+// src/frontend/parse.mkr:28:5
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_main(tokens: &[lexer::Token], pos: usize) -> Result<(types::Term, usize), ParseError> {
   let mut _token_pos = pos;
   let token1 = tokens.get(pos);
@@ -36,12 +40,16 @@ fn _parse_fn_main(tokens: &[lexer::Token], pos: usize) -> Result<(types::Term, u
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::STR(_), _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::GRAMMAR, _) => CodeType::Code0,
+      (lexer::TokenKind::STR(_), _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (head, pos) = _parse_fn_head(tokens, pos)?;
       let (_gr, pos) = _parse_fn_gr(tokens, pos)?;
@@ -50,6 +58,7 @@ fn _parse_fn_main(tokens: &[lexer::Token], pos: usize) -> Result<(types::Term, u
       let (_v, pos) = _parse_token_Tok_EOF(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:30:5
       let mut v = head;
       v.reverse();
       (v, setting, body)
@@ -63,10 +72,13 @@ fn _parse_fn_main(tokens: &[lexer::Token], pos: usize) -> Result<(types::Term, u
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:36:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_head(tokens: &[lexer::Token], pos: usize) -> Result<(types::Head, usize), ParseError> {
   let mut _token_pos = pos;
   let token1 = tokens.get(pos);
@@ -74,32 +86,42 @@ fn _parse_fn_head(tokens: &[lexer::Token], pos: usize) -> Result<(types::Head, u
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::STR(_), _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::STR(_), _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (tok, pos) = _parse_token_Tok_STR(tokens, pos)?;
       let (tail, pos) = _parse_fn_head_tail(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:38:5
       let mut tail_v = tail;
       let (stok, rng) = tok;
       let s = lexer::get_string(stok).unwrap();
       tail_v.push((rng, s));
       tail_v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:44:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:47:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_head_tail(
   tokens: &[lexer::Token],
   pos: usize,
@@ -110,32 +132,42 @@ fn _parse_fn_head_tail(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::STR(_), _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::STR(_), _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (head, pos) = _parse_token_Tok_STR(tokens, pos)?;
       let (tail, pos) = _parse_fn_head(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:49:5
       let mut tail_v = tail;
       let (stok, rng) = head;
       let s = lexer::get_string(stok).unwrap();
       tail_v.push((rng, s));
       tail_v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:55:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:58:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_gr(tokens: &[lexer::Token], pos: usize) -> Result<((), usize), ParseError> {
   let mut _token_pos = pos;
   let token1 = tokens.get(pos);
@@ -143,17 +175,21 @@ fn _parse_fn_gr(tokens: &[lexer::Token], pos: usize) -> Result<((), usize), Pars
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::GRAMMAR, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::GRAMMAR, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v1, pos) = _parse_token_Tok_GRAMMAR(tokens, pos)?;
       let (_v2, pos) = _parse_token_Tok_SEMICOLON(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:59:47
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
@@ -164,10 +200,13 @@ fn _parse_fn_gr(tokens: &[lexer::Token], pos: usize) -> Result<((), usize), Pars
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:62:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_setting(
   tokens: &[lexer::Token],
   pos: usize,
@@ -178,12 +217,15 @@ fn _parse_fn_setting(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::EXTERN, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::EXTERN, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v1, pos) = _parse_token_Tok_EXTERN(tokens, pos)?;
       let (_v2, pos) = _parse_token_Tok_LCURLYBRACES(tokens, pos)?;
@@ -191,6 +233,7 @@ fn _parse_fn_setting(
       let (_v3, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:63:88
       types
     }
     _ => {
@@ -202,10 +245,13 @@ fn _parse_fn_setting(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:66:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_types(
   tokens: &[lexer::Token],
   pos: usize,
@@ -216,12 +262,15 @@ fn _parse_fn_types(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::ENUM, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::ENUM, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v1, pos) = _parse_token_Tok_ENUM(tokens, pos)?;
       let (nametok, pos) = _parse_token_Tok_STR(tokens, pos)?;
@@ -230,11 +279,12 @@ fn _parse_fn_types(
       let (_v3, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
 
       _token_pos = pos;
-      let (stok, _) = nametok;
+      // src/frontend/parse.mkr:69:5
+      let (stok, rng) = nametok;
       let s = lexer::get_string(stok).unwrap();
       let mut settokens = settokens_rev;
       settokens.reverse();
-      (s, settokens)
+      ((rng, s), settokens)
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
@@ -245,10 +295,13 @@ fn _parse_fn_types(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:77:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_settokens(
   tokens: &[lexer::Token],
   pos: usize,
@@ -259,30 +312,40 @@ fn _parse_fn_settokens(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (settoken, pos) = _parse_fn_settoken(tokens, pos)?;
       let (settokens, pos) = _parse_fn_settokens_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:79:5
       let mut v = settokens;
       v.push(settoken);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:83:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:86:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_settokens_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -293,28 +356,38 @@ fn _parse_fn_settokens_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::COMMA, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::COMMA, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v, pos) = _parse_token_Tok_COMMA(tokens, pos)?;
       let (tail, pos) = _parse_fn_settokens_sub_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:87:49
       tail
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:88:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:91:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_settokens_sub_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -325,30 +398,40 @@ fn _parse_fn_settokens_sub_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (settoken, pos) = _parse_fn_settoken(tokens, pos)?;
       let (settokens, pos) = _parse_fn_settokens_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:93:5
       let mut v = settokens;
       v.push(settoken);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:97:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:100:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_settoken(
   tokens: &[lexer::Token],
   pos: usize,
@@ -359,23 +442,27 @@ fn _parse_fn_settoken(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (name, pos) = _parse_token_Tok_CONSTRUCTOR(tokens, pos)?;
       let (_v, pos) = _parse_token_Tok_ARROW(tokens, pos)?;
       let (typestr, pos) = _parse_token_Tok_STR(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:102:5
       let (v1tok, rng1) = name;
       let v1 = lexer::get_string(v1tok).unwrap();
       let (v2tok, rng2) = typestr;
       let v2 = lexer::get_string(v2tok).unwrap();
-      (types::Range::unite(rng1, rng2), v1, v2)
+      (types::Range::unite(rng1, rng2), v1, (rng2, v2))
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
@@ -386,10 +473,13 @@ fn _parse_fn_settoken(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:110:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_body(
   tokens: &[lexer::Token],
   pos: usize,
@@ -400,17 +490,22 @@ fn _parse_fn_body(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::PUB, _) => CodeType::Code0,
-    (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::EOF, _) => CodeType::Code0,
+      (lexer::TokenKind::PUB, _) => CodeType::Code0,
+      (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (bnflst, pos) = _parse_fn_bnflst(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:112:5
       let mut v = bnflst;
       v.reverse();
       v
@@ -424,10 +519,13 @@ fn _parse_fn_body(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:118:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnflst(
   tokens: &[lexer::Token],
   pos: usize,
@@ -438,31 +536,41 @@ fn _parse_fn_bnflst(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::PUB, _) => CodeType::Code0,
-    (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::PUB, _) => CodeType::Code0,
+      (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (bnf, pos) = _parse_fn_bnf(tokens, pos)?;
       let (bnflst, pos) = _parse_fn_bnflst_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:120:5
       let mut v = bnflst;
       v.push(bnf);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:124:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:127:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnflst_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -473,28 +581,38 @@ fn _parse_fn_bnflst_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::SEMICOLON, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::SEMICOLON, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v, pos) = _parse_token_Tok_SEMICOLON(tokens, pos)?;
       let (tail, pos) = _parse_fn_bnflst_sub_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:128:50
       tail
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:129:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:132:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnflst_sub_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -505,31 +623,41 @@ fn _parse_fn_bnflst_sub_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::PUB, _) => CodeType::Code0,
-    (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::PUB, _) => CodeType::Code0,
+      (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (bnf, pos) = _parse_fn_bnf(tokens, pos)?;
       let (bnflst, pos) = _parse_fn_bnflst_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:134:5
       let mut v = bnflst;
       v.push(bnf);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:138:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:141:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnf(tokens: &[lexer::Token], pos: usize) -> Result<(types::Bnf, usize), ParseError> {
   let mut _token_pos = pos;
   let token1 = tokens.get(pos);
@@ -538,51 +666,115 @@ fn _parse_fn_bnf(tokens: &[lexer::Token], pos: usize) -> Result<(types::Bnf, usi
     Code1,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::PUB, _) => CodeType::Code0,
-    (lexer::TokenKind::VAR(_), _) => CodeType::Code1,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::PUB, _) => CodeType::Code0,
+      (lexer::TokenKind::VAR(_), _) => CodeType::Code1,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v1, pos) = _parse_token_Tok_PUB(tokens, pos)?;
       let (fnname, pos) = _parse_token_Tok_VAR(tokens, pos)?;
-      let (_v2, pos) = _parse_token_Tok_COLON(tokens, pos)?;
-      let (typestr, pos) = _parse_token_Tok_STR(tokens, pos)?;
-      let (_v3, pos) = _parse_token_Tok_EQ(tokens, pos)?;
-      let (_v4, pos) = _parse_token_Tok_LCURLYBRACES(tokens, pos)?;
-      let (bnf_code_lst, pos) = _parse_fn_bnf_code_lst(tokens, pos)?;
-      let (v5, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
+      let (body, pos) = _parse_fn_bnf_body(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:143:5
       let (nametok, rng1) = fnname;
       let name = lexer::get_string(nametok).unwrap();
-      let (stok, _) = typestr;
-      let s = lexer::get_string(stok).unwrap();
-      let (_, rng2) = v5;
+      let (typestr_opt, bnf_code_lst, rng2) = body;
       let rng = types::Range::unite(rng1, rng2);
-      types::Bnf::Pub(rng, name, s, bnf_code_lst)
+      let typestr = typestr_opt.unwrap_or((rng1, String::new()));
+      types::Bnf::Pub(rng, name, typestr, bnf_code_lst)
     }
     CodeType::Code1 => {
       let (fnname, pos) = _parse_token_Tok_VAR(tokens, pos)?;
-      let (_v2, pos) = _parse_token_Tok_COLON(tokens, pos)?;
-      let (typestr, pos) = _parse_token_Tok_STR(tokens, pos)?;
-      let (_v3, pos) = _parse_token_Tok_EQ(tokens, pos)?;
-      let (_v4, pos) = _parse_token_Tok_LCURLYBRACES(tokens, pos)?;
-      let (bnf_code_lst_rev, pos) = _parse_fn_bnf_code_lst(tokens, pos)?;
-      let (v5, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
+      let (body, pos) = _parse_fn_bnf_body(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:151:5
       let (nametok, rng1) = fnname;
       let name = lexer::get_string(nametok).unwrap();
-      let (stok, _) = typestr;
-      let s = lexer::get_string(stok).unwrap();
-      let (_, rng2) = v5;
+      let (typestr_opt, bnf_code_lst, rng2) = body;
       let rng = types::Range::unite(rng1, rng2);
+      let typestr = typestr_opt.unwrap_or((rng1, String::new()));
+      types::Bnf::NonPub(rng, name, typestr, bnf_code_lst)
+    }
+    _ => {
+      return Err(ParseError::UnexpectedToken(
+        tokens.iter().next().unwrap().clone(),
+      ))
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:160:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_bnf_body(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<
+  (
+    (Option<types::TypeStr>, Vec<types::Code>, types::Range),
+    usize,
+  ),
+  ParseError,
+> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Code1,
+    Other,
+  }
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::COLON, _) => CodeType::Code0,
+      (lexer::TokenKind::EQ, _) => CodeType::Code1,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (_v1, pos) = _parse_token_Tok_COLON(tokens, pos)?;
+      let (typestr, pos) = _parse_token_Tok_STR(tokens, pos)?;
+      let (_v2, pos) = _parse_token_Tok_EQ(tokens, pos)?;
+      let (_v3, pos) = _parse_token_Tok_LCURLYBRACES(tokens, pos)?;
+      let (bnf_code_lst_rev, pos) = _parse_fn_bnf_code_lst(tokens, pos)?;
+      let (v4, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:163:5
+      let (stok, typestr_rng) = typestr;
+      let s = lexer::get_string(stok).unwrap();
+      let (_, rng) = v4;
       let mut bnf_code_lst = bnf_code_lst_rev;
       bnf_code_lst.reverse();
-      types::Bnf::NonPub(rng, name, s, bnf_code_lst)
+      (Some((typestr_rng, s)), bnf_code_lst, rng)
+    }
+    CodeType::Code1 => {
+      let (_v1, pos) = _parse_token_Tok_EQ(tokens, pos)?;
+      let (_v2, pos) = _parse_token_Tok_LCURLYBRACES(tokens, pos)?;
+      let (auto_code_lst_rev, pos) = _parse_fn_auto_code_lst(tokens, pos)?;
+      let (v3, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:172:5
+      let (_, rng) = v3;
+      let mut auto_code_lst = auto_code_lst_rev;
+      auto_code_lst.reverse();
+      (None, auto_code_lst, rng)
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
@@ -593,10 +785,13 @@ fn _parse_fn_bnf(tokens: &[lexer::Token], pos: usize) -> Result<(types::Bnf, usi
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:179:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnf_code_lst(
   tokens: &[lexer::Token],
   pos: usize,
@@ -607,31 +802,41 @@ fn _parse_fn_bnf_code_lst(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::ARROW, _) => CodeType::Code0,
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code0,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (bnf_code, pos) = _parse_fn_bnf_code(tokens, pos)?;
       let (bnf_code_lst, pos) = _parse_fn_bnf_code_lst_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:181:5
       let mut v = bnf_code_lst;
       v.push(bnf_code);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:185:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:188:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnf_code_lst_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -642,28 +847,38 @@ fn _parse_fn_bnf_code_lst_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::COMMA, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::COMMA, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v, pos) = _parse_token_Tok_COMMA(tokens, pos)?;
       let (tail, pos) = _parse_fn_bnf_code_lst_sub_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:189:52
       tail
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:190:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:193:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnf_code_lst_sub_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -674,31 +889,41 @@ fn _parse_fn_bnf_code_lst_sub_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::ARROW, _) => CodeType::Code0,
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code0,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (bnf_code, pos) = _parse_fn_bnf_code(tokens, pos)?;
       let (bnf_code_lst, pos) = _parse_fn_bnf_code_lst_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:195:5
       let mut v = bnf_code_lst;
       v.push(bnf_code);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:199:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:202:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_bnf_code(
   tokens: &[lexer::Token],
   pos: usize,
@@ -710,13 +935,16 @@ fn _parse_fn_bnf_code(
     Code1,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::ARROW, _) => CodeType::Code1,
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code1,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (fn_or_token, pos) = _parse_fn_fn_or_token(tokens, pos)?;
       let (fn_or_tokens, pos) = _parse_fn_fn_or_token_lst(tokens, pos)?;
@@ -726,12 +954,13 @@ fn _parse_fn_bnf_code(
       let (_v3, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
 
       _token_pos = pos;
-      let (codetok, _) = code;
+      // src/frontend/parse.mkr:205:5
+      let (codetok, coderng) = code;
       let codestr = lexer::get_string(codetok).unwrap();
       let mut v = fn_or_tokens;
       v.push(fn_or_token);
       v.reverse();
-      (v, codestr)
+      (v, (coderng, codestr))
     }
     CodeType::Code1 => {
       let (_v1, pos) = _parse_token_Tok_ARROW(tokens, pos)?;
@@ -740,11 +969,12 @@ fn _parse_fn_bnf_code(
       let (_v3, pos) = _parse_token_Tok_RCURLYBRACES(tokens, pos)?;
 
       _token_pos = pos;
-      let (codetok, _) = code;
+      // src/frontend/parse.mkr:213:5
+      let (codetok, coderng) = code;
       let codestr = lexer::get_string(codetok).unwrap();
       let mut v = Vec::new();
       v.reverse();
-      (v, codestr)
+      (v, (coderng, codestr))
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
@@ -755,10 +985,249 @@ fn _parse_fn_bnf_code(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:221:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_auto_code_lst(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<(Vec<types::Code>, usize), ParseError> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Other,
+  }
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code0,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (auto_code, pos) = _parse_fn_auto_code(tokens, pos)?;
+      let (auto_code_lst, pos) = _parse_fn_auto_code_lst_sub(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:223:5
+      let mut v = auto_code_lst;
+      v.push(auto_code);
+      v
+    }
+    _ => {
+      // src/frontend/parse.mkr:227:7
+      Vec::new()
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:230:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_auto_code_lst_sub(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<(Vec<types::Code>, usize), ParseError> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Other,
+  }
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::COMMA, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (_v, pos) = _parse_token_Tok_COMMA(tokens, pos)?;
+      let (tail, pos) = _parse_fn_auto_code_lst_sub_sub(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:231:53
+      tail
+    }
+    _ => {
+      // src/frontend/parse.mkr:232:7
+      Vec::new()
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:235:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_auto_code_lst_sub_sub(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<(Vec<types::Code>, usize), ParseError> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Other,
+  }
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code0,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (auto_code, pos) = _parse_fn_auto_code(tokens, pos)?;
+      let (auto_code_lst, pos) = _parse_fn_auto_code_lst_sub(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:237:5
+      let mut v = auto_code_lst;
+      v.push(auto_code);
+      v
+    }
+    _ => {
+      // src/frontend/parse.mkr:241:7
+      Vec::new()
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:244:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_auto_code(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<(types::Code, usize), ParseError> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Code1,
+    Other,
+  }
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code1,
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (fn_or_token, pos) = _parse_fn_fn_or_token(tokens, pos)?;
+      let (fn_or_tokens, pos) = _parse_fn_fn_or_token_lst(tokens, pos)?;
+      let (label_opt, pos) = _parse_fn_auto_label(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:246:5
+      let rng = fn_or_token.1.range();
+      let mut v = fn_or_tokens;
+      v.push(fn_or_token);
+      v.reverse();
+      (v, label_opt.unwrap_or((rng, String::new())))
+    }
+    CodeType::Code1 => {
+      let (_v1, pos) = _parse_token_Tok_ARROW(tokens, pos)?;
+      let (label, pos) = _parse_token_Tok_CONSTRUCTOR(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:253:5
+      let (labeltok, labelrng) = label;
+      let labelstr = lexer::get_string(labeltok).unwrap();
+      (Vec::new(), (labelrng, labelstr))
+    }
+    _ => {
+      return Err(ParseError::UnexpectedToken(
+        tokens.iter().next().unwrap().clone(),
+      ))
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:259:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
+fn _parse_fn_auto_label(
+  tokens: &[lexer::Token],
+  pos: usize,
+) -> Result<(Option<types::CodeStr>, usize), ParseError> {
+  let mut _token_pos = pos;
+  let token1 = tokens.get(pos);
+  enum CodeType {
+    Code0,
+    Other,
+  }
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::ARROW, _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
+    CodeType::Code0 => {
+      let (_v1, pos) = _parse_token_Tok_ARROW(tokens, pos)?;
+      let (label, pos) = _parse_token_Tok_CONSTRUCTOR(tokens, pos)?;
+
+      _token_pos = pos;
+      // src/frontend/parse.mkr:261:5
+      let (labeltok, labelrng) = label;
+      let labelstr = lexer::get_string(labeltok).unwrap();
+      Some((labelrng, labelstr))
+    }
+    _ => {
+      // src/frontend/parse.mkr:265:7
+      None
+    }
+  };
+  Ok((main, _token_pos))
+}
+
+// src/frontend/parse.mkr:268:1
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(unused_parens)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_fn_or_token_lst(
   tokens: &[lexer::Token],
   pos: usize,
@@ -769,30 +1238,40 @@ fn _parse_fn_fn_or_token_lst(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (f, pos) = _parse_fn_fn_or_token(tokens, pos)?;
       let (fs, pos) = _parse_fn_fn_or_token_lst_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:270:5
       let mut v = fs;
       v.push(f);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:274:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:277:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_fn_or_token_lst_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -803,30 +1282,40 @@ fn _parse_fn_fn_or_token_lst_sub(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => CodeType::Other,
+    Some(tok) => match tok {
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (f, pos) = _parse_fn_fn_or_token(tokens, pos)?;
       let (fs, pos) = _parse_fn_fn_or_token_lst(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:279:5
       let mut v = fs;
       v.push(f);
       v
     }
-    _ => Vec::new(),
+    _ => {
+      // src/frontend/parse.mkr:283:7
+      Vec::new()
+    }
   };
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:286:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_fn_or_token(
   tokens: &[lexer::Token],
   pos: usize,
@@ -837,12 +1326,15 @@ fn _parse_fn_fn_or_token(
     Code0,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::LBRACES, _) => CodeType::Code0,
 
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (_v1, pos) = _parse_token_Tok_LBRACES(tokens, pos)?;
       let (name, pos) = _parse_token_Tok_VAR(tokens, pos)?;
@@ -850,6 +1342,7 @@ fn _parse_fn_fn_or_token(
       let (tail, pos) = _parse_fn_fn_or_token_sub(tokens, pos)?;
 
       _token_pos = pos;
+      // src/frontend/parse.mkr:288:5
       let (nametok, _) = name;
       let namestr = lexer::get_string(nametok).unwrap();
       (namestr, tail)
@@ -863,10 +1356,13 @@ fn _parse_fn_fn_or_token(
   Ok((main, _token_pos))
 }
 
+// src/frontend/parse.mkr:294:1
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::type_complexity)]
+#[allow(clippy::let_unit_value)]
+#[allow(clippy::collapsible_match)]
 fn _parse_fn_fn_or_token_sub(
   tokens: &[lexer::Token],
   pos: usize,
@@ -878,30 +1374,35 @@ fn _parse_fn_fn_or_token_sub(
     Code1,
     Other,
   }
-  let code_type = token1.ok_or(ParseError::Eof).map(|tok| match tok {
-    (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code1,
-    (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
-
-    _ => CodeType::Other,
-  });
-  let main = match code_type? {
+  let code_type = match token1 {
+    None => return Err(ParseError::Eof),
+    Some(tok) => match tok {
+      (lexer::TokenKind::CONSTRUCTOR(_), _) => CodeType::Code1,
+      (lexer::TokenKind::VAR(_), _) => CodeType::Code0,
+
+      _ => CodeType::Other,
+    },
+  };
+  let main = match code_type {
     CodeType::Code0 => {
       let (fnname, pos) = _parse_token_Tok_VAR(tokens, pos)?;
       let (_v3, pos) = _parse_token_Tok_RBRACES(tokens, pos)?;
 
       _token_pos = pos;
-      let (fnnametok, _) = fnname;
+      // src/frontend/parse.mkr:296:5
+      let (fnnametok, rng) = fnname;
       let fnnamestr = lexer::get_string(fnnametok).unwrap();
-      types::FnOrToken::Function(fnnamestr)
+      types::FnOrToken::Function(rng, fnnamestr)
     }
     CodeType::Code1 => {
       let (tokname, pos) = _parse_token_Tok_CONSTRUCTOR(tokens, pos)?;
       let (_v3, pos) = _parse_token_Tok_RBRACES(tokens, pos)?;
 
       _token_pos = pos;
-      let (toknametok, _) = tokname;
+      // src/frontend/parse.mkr:301:5
+      let (toknametok, rng) = tokname;
       let toknamestr = lexer::get_string(toknametok).unwrap();
-      types::FnOrToken::Token(toknamestr)
+      types::FnOrToken::Token(rng, toknamestr)
     }
     _ => {
       return Err(ParseError::UnexpectedToken(
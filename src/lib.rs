@@ -0,0 +1,9 @@
+pub mod backend;
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod frontend;
+pub mod lsp;
+pub mod types;
+
+pub use config::Config;
@@ -0,0 +1,589 @@
+pub mod json;
+
+use super::backend::{self, analysis, ast_type, cst, symbols};
+use super::{error, frontend, types};
+use json::Json;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// 標準入出力でやり取りする、文法ファイルのための言語サーバ（Language Server Protocol）
+// 文書は変更のたびに全体を送ってもらい、そのたびに読み込み直して検査する
+
+// リクエストへの結果、またはエラーのコードとメッセージ
+type ResponseResult = Result<Json, (i32, String)>;
+
+// 開いている文書
+struct Document {
+  text: String,
+  // 読み込めなかったときはNone
+  term_opt: Option<types::Term>,
+}
+
+/// 言語サーバの状態
+pub struct Server {
+  document_map: HashMap<String, Document>,
+  is_shutdown: bool,
+}
+
+impl Default for Server {
+  fn default() -> Self {
+    Server::new()
+  }
+}
+
+impl Server {
+  pub fn new() -> Server {
+    Server {
+      document_map: HashMap::new(),
+      is_shutdown: false,
+    }
+  }
+
+  /// `shutdown`を受け取っていればtrue
+  pub fn is_shutdown(&self) -> bool {
+    self.is_shutdown
+  }
+
+  /// 受け取ったメッセージを処理し、送り返すメッセージを返す
+  pub fn handle_message(&mut self, message: &Json) -> Vec<Json> {
+    let method = message.get("method").as_str().unwrap_or("");
+    let params = message.get("params");
+    let id = message.get("id");
+    let is_request = id != &Json::Null;
+    let result = match method {
+      "initialize" => Ok(make_initialize_result()),
+      "shutdown" => {
+        self.is_shutdown = true;
+        Ok(Json::Null)
+      }
+      "textDocument/didOpen" => {
+        let text_document = params.get("textDocument");
+        let text = text_document.get("text").as_str().unwrap_or("");
+        return self.update_document(text_document.get("uri"), text);
+      }
+      "textDocument/didChange" => {
+        // 文書全体を送ってもらうので、最後の変更だけを見ればよい
+        let change_lst = params.get("contentChanges").as_array();
+        match change_lst.and_then(|v| v.last()) {
+          Some(change) => {
+            let text = change.get("text").as_str().unwrap_or("");
+            return self.update_document(params.get("textDocument").get("uri"), text);
+          }
+          None => return Vec::new(),
+        }
+      }
+      "textDocument/didClose" => {
+        let uri = params.get("textDocument").get("uri");
+        if let Some(s) = uri.as_str() {
+          self.document_map.remove(s);
+        }
+        return vec![make_diagnostics_notification(uri, Vec::new())];
+      }
+      "textDocument/definition" => {
+        self.with_occurrence(params, |document, occurrence_lst, occurrence| {
+          let uri = params.get("textDocument").get("uri");
+          Ok(
+            symbols::find_same_symbol(occurrence_lst, occurrence.kind, &occurrence.name)
+              .into_iter()
+              .find(|o| o.is_definition)
+              .map_or(Json::Null, |o| make_location(uri, &document.text, &o.range)),
+          )
+        })
+      }
+      "textDocument/references" => {
+        self.with_occurrence(params, |document, occurrence_lst, occurrence| {
+          let uri = params.get("textDocument").get("uri");
+          let is_include_declaration = params
+            .get("context")
+            .get("includeDeclaration")
+            .as_bool()
+            .unwrap_or(true);
+          Ok(Json::Array(
+            symbols::find_same_symbol(occurrence_lst, occurrence.kind, &occurrence.name)
+              .into_iter()
+              .filter(|o| is_include_declaration || !o.is_definition)
+              .map(|o| make_location(uri, &document.text, &o.range))
+              .collect(),
+          ))
+        })
+      }
+      "textDocument/hover" => self.with_occurrence(params, |document, _, occurrence| {
+        Ok(match make_hover_str(document, occurrence) {
+          Some(s) => Json::object(vec![
+            (
+              "contents",
+              Json::object(vec![
+                ("kind", Json::str("markdown")),
+                ("value", Json::Str(s)),
+              ]),
+            ),
+            ("range", make_range(&document.text, &occurrence.range)),
+          ]),
+          None => Json::Null,
+        })
+      }),
+      "textDocument/rename" => self.with_occurrence(params, |document, _, occurrence| {
+        let uri = params.get("textDocument").get("uri");
+        let new_name = params.get("newName").as_str().unwrap_or("");
+        let term = document.term_opt.as_ref().unwrap();
+        match symbols::make_rename_range_lst(term, &occurrence.name, new_name) {
+          Ok(range_lst) => {
+            let edit_lst = range_lst
+              .iter()
+              .map(|rng| {
+                Json::object(vec![
+                  ("range", make_range(&document.text, rng)),
+                  ("newText", Json::str(new_name)),
+                ])
+              })
+              .collect();
+            Ok(Json::object(vec![(
+              "changes",
+              Json::Object(vec![(
+                uri.as_str().unwrap_or("").to_string(),
+                Json::Array(edit_lst),
+              )]),
+            )]))
+          }
+          Err(e) => Err((
+            -32602,
+            error::make_error_msg(e, Some(get_file_name(uri)), Some(&document.text)),
+          )),
+        }
+      }),
+      "textDocument/documentSymbol" => {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        Ok(match self.document_map.get(uri) {
+          Some(Document {
+            text,
+            term_opt: Some(term),
+          }) => make_document_symbols(text, term),
+          _ => Json::Null,
+        })
+      }
+      _ => Err((-32601, format!("method not found: {}", method))),
+    };
+    if !is_request {
+      return Vec::new();
+    }
+    let response = match result {
+      Ok(result) => Json::object(vec![
+        ("jsonrpc", Json::str("2.0")),
+        ("id", id.clone()),
+        ("result", result),
+      ]),
+      Err((code, msg)) => make_error_response(id.clone(), code, &msg),
+    };
+    vec![response]
+  }
+
+  // 文書を読み込み直し、診断結果を送る
+  fn update_document(&mut self, uri: &Json, text: &str) -> Vec<Json> {
+    let uri_str = match uri.as_str() {
+      Some(s) => s,
+      None => return Vec::new(),
+    };
+    let file_name = get_file_name(uri);
+    let (term_opt, diagnostic_lst) = match frontend::get_ast(text) {
+      Ok(term) => {
+        let diagnostic_lst = match backend::to_string(term.clone(), file_name, text) {
+          Ok(_) => match backend::check_analysis(&term) {
+            Ok(()) => Vec::new(),
+            // Warning
+            Err(e) => vec![make_diagnostic(file_name, text, e, 2)],
+          },
+          // Error
+          Err(e) => vec![make_diagnostic(file_name, text, e, 1)],
+        };
+        (Some(term), diagnostic_lst)
+      }
+      Err(e) => (None, vec![make_diagnostic(file_name, text, e, 1)]),
+    };
+    self.document_map.insert(
+      uri_str.to_string(),
+      Document {
+        text: text.to_string(),
+        term_opt,
+      },
+    );
+    vec![make_diagnostics_notification(uri, diagnostic_lst)]
+  }
+
+  // カーソルの位置にある規則やトークンの名前についてfを呼ぶ
+  // 名前の上に無いときや、文書を読み込めていないときはnullを返す
+  fn with_occurrence<F>(&self, params: &Json, f: F) -> ResponseResult
+  where
+    F: FnOnce(&Document, &[symbols::Occurrence], &symbols::Occurrence) -> ResponseResult,
+  {
+    let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+    let document = match self.document_map.get(uri) {
+      Some(document) => document,
+      None => return Ok(Json::Null),
+    };
+    let term = match &document.term_opt {
+      Some(term) => term,
+      None => return Ok(Json::Null),
+    };
+    let pos = match position_to_offset(&document.text, params.get("position")) {
+      Some(pos) => pos,
+      None => return Ok(Json::Null),
+    };
+    let occurrence_lst = symbols::make_occurrence_lst(term);
+    match symbols::find_occurrence_at(&occurrence_lst, pos) {
+      Some(occurrence) => f(document, &occurrence_lst, occurrence),
+      None => Ok(Json::Null),
+    }
+  }
+}
+
+// 診断結果などに表示するファイル名
+fn get_file_name(uri: &Json) -> &str {
+  uri.as_str().unwrap_or("").trim_start_matches("file://")
+}
+
+fn make_initialize_result() -> Json {
+  Json::object(vec![
+    (
+      "capabilities",
+      Json::object(vec![
+        // 変更のたびに文書全体を送ってもらう
+        ("textDocumentSync", Json::number(1)),
+        ("definitionProvider", Json::Bool(true)),
+        ("referencesProvider", Json::Bool(true)),
+        ("hoverProvider", Json::Bool(true)),
+        ("documentSymbolProvider", Json::Bool(true)),
+        ("renameProvider", Json::Bool(true)),
+      ]),
+    ),
+    (
+      "serverInfo",
+      Json::object(vec![
+        ("name", Json::str("llmaker")),
+        ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+      ]),
+    ),
+  ])
+}
+
+fn make_error_response(id: Json, code: i32, msg: &str) -> Json {
+  Json::object(vec![
+    ("jsonrpc", Json::str("2.0")),
+    ("id", id),
+    (
+      "error",
+      Json::object(vec![
+        ("code", Json::Number(code as f64)),
+        ("message", Json::str(msg)),
+      ]),
+    ),
+  ])
+}
+
+fn make_diagnostics_notification(uri: &Json, diagnostic_lst: Vec<Json>) -> Json {
+  Json::object(vec![
+    ("jsonrpc", Json::str("2.0")),
+    ("method", Json::str("textDocument/publishDiagnostics")),
+    (
+      "params",
+      Json::object(vec![
+        ("uri", uri.clone()),
+        ("diagnostics", Json::Array(diagnostic_lst)),
+      ]),
+    ),
+  ])
+}
+
+// 位置情報の無いエラーは文書の末尾に付ける
+// severityは1がError、2がWarning
+fn make_diagnostic(file_name: &str, text: &str, err: error::Error, severity: usize) -> Json {
+  let rng = match error::get_error_range(&err) {
+    Some(rng) => rng,
+    None => types::Range::make(text.len(), 0),
+  };
+  Json::object(vec![
+    ("range", make_range(text, &rng)),
+    ("severity", Json::number(severity)),
+    ("source", Json::str("llmaker")),
+    (
+      "message",
+      Json::Str(error::make_error_msg(err, Some(file_name), Some(text))),
+    ),
+  ])
+}
+
+// LSPの位置は0から数えた行と、その行の先頭からのUTF-16での長さで表す
+fn offset_to_position(text: &str, offset: usize) -> Json {
+  let before = &text[..offset.min(text.len())];
+  let line = before.matches('\n').count();
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  let character = before[line_start..].encode_utf16().count();
+  Json::object(vec![
+    ("line", Json::number(line)),
+    ("character", Json::number(character)),
+  ])
+}
+
+fn position_to_offset(text: &str, position: &Json) -> Option<usize> {
+  let line = position.get("line").as_usize()?;
+  let character = position.get("character").as_usize()?;
+  let mut line_start = 0;
+  for _ in 0..line {
+    line_start += text[line_start..].find('\n')? + 1;
+  }
+  let line_end = text[line_start..]
+    .find('\n')
+    .map_or(text.len(), |i| line_start + i);
+  let mut utf16_count = 0;
+  for (i, c) in text[line_start..line_end].char_indices() {
+    if utf16_count >= character {
+      return Some(line_start + i);
+    }
+    utf16_count += c.len_utf16();
+  }
+  Some(line_end)
+}
+
+fn make_range(text: &str, rng: &types::Range) -> Json {
+  let (start, end) = rng.to_tuple();
+  Json::object(vec![
+    ("start", offset_to_position(text, start)),
+    ("end", offset_to_position(text, end)),
+  ])
+}
+
+fn make_location(uri: &Json, text: &str, rng: &types::Range) -> Json {
+  Json::object(vec![("uri", uri.clone()), ("range", make_range(text, rng))])
+}
+
+fn get_source_str<'a>(text: &'a str, rng: &types::Range) -> &'a str {
+  let (start, end) = rng.to_tuple();
+  &text[start..end]
+}
+
+// 規則なら型とFIRST集合、トークンならパターンを表示する
+fn make_hover_str(document: &Document, occurrence: &symbols::Occurrence) -> Option<String> {
+  let (_, (_, token_lst), bnfs) = document.term_opt.as_ref()?;
+  match occurrence.kind {
+    symbols::SymbolKind::Rule => {
+      let bnf = bnfs.iter().find(|bnf| match bnf {
+        types::Bnf::Pub(_, name, _, _) | types::Bnf::NonPub(_, name, _, _) => {
+          name == &occurrence.name
+        }
+      })?;
+      // 型を省略した規則は、生成される型の名前を表示する
+      let type_str = match bnf {
+        types::Bnf::Pub(_, _, (type_rng, _), _) | types::Bnf::NonPub(_, _, (type_rng, _), _) => {
+          if ast_type::is_auto_rule(bnf) {
+            cst::to_type_name(&occurrence.name)
+          } else {
+            get_source_str(&document.text, type_rng).to_string()
+          }
+        }
+      };
+      let first_set_map = analysis::make_first_set_map(bnfs);
+      let nullable_str = if analysis::make_nullable_set(bnfs).contains(&occurrence.name) {
+        " (can be empty)"
+      } else {
+        ""
+      };
+      Some(format!(
+        "```\n{}: {}\n```\nFIRST = {}{}",
+        occurrence.name,
+        type_str,
+        analysis::set_to_str(&first_set_map[&occurrence.name]),
+        nullable_str
+      ))
+    }
+    symbols::SymbolKind::Token => {
+      let (_, _, (pattern_rng, _)) = token_lst
+        .iter()
+        .find(|(_, name, _)| name == &occurrence.name)?;
+      Some(format!(
+        "```\n{} => {}\n```",
+        occurrence.name,
+        get_source_str(&document.text, pattern_rng)
+      ))
+    }
+  }
+}
+
+fn make_document_symbol(
+  text: &str,
+  name: &str,
+  detail: &str,
+  kind: usize,
+  rng: &types::Range,
+  selection_rng: &types::Range,
+  children: Vec<Json>,
+) -> Json {
+  Json::object(vec![
+    ("name", Json::str(name)),
+    ("detail", Json::str(detail)),
+    ("kind", Json::number(kind)),
+    ("range", make_range(text, rng)),
+    ("selectionRange", make_range(text, selection_rng)),
+    ("children", Json::Array(children)),
+  ])
+}
+
+// SymbolKindの番号
+const SYMBOL_KIND_ENUM: usize = 10;
+const SYMBOL_KIND_FUNCTION: usize = 12;
+const SYMBOL_KIND_ENUM_MEMBER: usize = 22;
+
+// extern の中のトークンをまとめたものと、各規則を返す
+fn make_document_symbols(text: &str, term: &types::Term) -> Json {
+  let (_, ((type_rng, _), token_lst), bnfs) = term;
+  let mut symbol_lst = Vec::new();
+  let token_symbol_lst: Vec<Json> = token_lst
+    .iter()
+    .map(|(rng, name, (pattern_rng, _))| {
+      make_document_symbol(
+        text,
+        name,
+        get_source_str(text, pattern_rng),
+        SYMBOL_KIND_ENUM_MEMBER,
+        rng,
+        &types::Range::make(rng.to_tuple().0, name.len()),
+        Vec::new(),
+      )
+    })
+    .collect();
+  let extern_rng = token_lst
+    .iter()
+    .fold(*type_rng, |rng, (token_rng, _, _)| rng.merge(token_rng));
+  symbol_lst.push(make_document_symbol(
+    text,
+    get_source_str(text, type_rng),
+    "extern",
+    SYMBOL_KIND_ENUM,
+    &extern_rng,
+    type_rng,
+    token_symbol_lst,
+  ));
+  for bnf in bnfs.iter() {
+    let (rng, name, type_rng) = match bnf {
+      types::Bnf::Pub(rng, name, (type_rng, _), _) => (rng, name, type_rng),
+      types::Bnf::NonPub(rng, name, (type_rng, _), _) => (rng, name, type_rng),
+    };
+    symbol_lst.push(make_document_symbol(
+      text,
+      name,
+      get_source_str(text, type_rng),
+      SYMBOL_KIND_FUNCTION,
+      rng,
+      &types::Range::make(rng.to_tuple().0, name.len()),
+      Vec::new(),
+    ))
+  }
+  Json::Array(symbol_lst)
+}
+
+// ヘッダを読み、Content-Lengthの長さの本文を返す
+// 入力が終わっていればNone
+fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+  let mut content_length_opt = None;
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    let line = line.trim_end();
+    if line.is_empty() {
+      if content_length_opt.is_some() {
+        break;
+      }
+      continue;
+    }
+    if let Some(value) = line.strip_prefix("Content-Length:") {
+      content_length_opt = value.trim().parse::<usize>().ok();
+    }
+  }
+  let mut body = vec![0; content_length_opt.unwrap_or(0)];
+  reader.read_exact(&mut body)?;
+  Ok(Some(String::from_utf8_lossy(&body).to_string()))
+}
+
+fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+  let body = message.to_string();
+  write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  writer.flush()
+}
+
+/// `exit`を受け取るか入力が終わるまで、メッセージを読んで処理する
+///
+/// 終了コードとして、`shutdown`の後に`exit`を受け取っていれば0、そうでなければ1を返す。
+pub fn run<R: BufRead, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<i32> {
+  let mut server = Server::new();
+  while let Some(body) = read_message(reader)? {
+    let message = match json::parse(&body) {
+      Ok(message) => message,
+      Err(_) => {
+        write_message(
+          writer,
+          &make_error_response(Json::Null, -32700, "parse error"),
+        )?;
+        continue;
+      }
+    };
+    if message.get("method").as_str() == Some("exit") {
+      return Ok(if server.is_shutdown() { 0 } else { 1 });
+    }
+    for response in server.handle_message(&message).iter() {
+      write_message(writer, response)?;
+    }
+  }
+  Ok(1)
+}
+
+#[test]
+fn check_server() {
+  let text = "grammar;
+extern {
+  enum \"Token\" {
+    Tok_A => \"'a'\",
+  }
+}
+pub main: \"()\" = {
+  <_x: sub> => {\"()\"},
+};
+sub: \"()\" = {
+  <_a: Tok_A> => {\"()\"},
+  => {\"()\"},
+};
+";
+  let mut server = Server::new();
+  let open = json::parse(&format!(
+    r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": "file:///a.mkr", "text": {}}}}}}}"#,
+    backend::source_map::escape_json_str(text)
+  ))
+  .unwrap();
+  let notification_lst = server.handle_message(&open);
+  assert_eq!(
+    notification_lst[0].get("params").get("diagnostics"),
+    &Json::Array(Vec::new())
+  );
+  // `<_x: sub>`の`sub`から定義へ
+  let definition = json::parse(
+    r#"{"jsonrpc": "2.0", "id": 1, "method": "textDocument/definition", "params": {"textDocument": {"uri": "file:///a.mkr"}, "position": {"line": 7, "character": 8}}}"#,
+  )
+  .unwrap();
+  let response_lst = server.handle_message(&definition);
+  assert_eq!(
+    response_lst[0].get("result").get("range").to_string(),
+    r#"{"start":{"line":9,"character":0},"end":{"line":9,"character":3}}"#
+  );
+  let hover = json::parse(
+    r#"{"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {"textDocument": {"uri": "file:///a.mkr"}, "position": {"line": 9, "character": 1}}}"#,
+  )
+  .unwrap();
+  let response_lst = server.handle_message(&hover);
+  assert_eq!(
+    response_lst[0]
+      .get("result")
+      .get("contents")
+      .get("value")
+      .as_str(),
+    Some("```\nsub: \"()\"\n```\nFIRST = {Tok_A} (can be empty)")
+  );
+}
@@ -0,0 +1,259 @@
+use super::super::backend::source_map::escape_json_str;
+use std::fmt;
+
+// 言語サーバとエディタの間でやり取りするJSONの値
+// オブジェクトのキーの順番は読み込んだ順、書き込んだ順のままにする
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+  Null,
+  Bool(bool),
+  Number(f64),
+  Str(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+  /// キーと値の組からオブジェクトを作る
+  pub fn object(pairs: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+      pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect(),
+    )
+  }
+
+  pub fn str(s: &str) -> Json {
+    Json::Str(s.to_string())
+  }
+
+  pub fn number(n: usize) -> Json {
+    Json::Number(n as f64)
+  }
+
+  /// オブジェクトのキーに対応する値（無ければ`Json::Null`）
+  pub fn get(&self, key: &str) -> &Json {
+    match self {
+      Json::Object(pairs) => pairs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map_or(&Json::Null, |(_, value)| value),
+      _ => &Json::Null,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Json::Str(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_usize(&self) -> Option<usize> {
+    match self {
+      Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+      _ => None,
+    }
+  }
+
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      Json::Bool(b) => Some(*b),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&Vec<Json>> {
+    match self {
+      Json::Array(v) => Some(v),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for Json {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Json::Null => write!(f, "null"),
+      Json::Bool(b) => write!(f, "{}", b),
+      Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+      Json::Number(n) => write!(f, "{}", n),
+      Json::Str(s) => write!(f, "{}", escape_json_str(s)),
+      Json::Array(v) => {
+        write!(f, "[")?;
+        for (i, value) in v.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}", value)?;
+        }
+        write!(f, "]")
+      }
+      Json::Object(pairs) => {
+        write!(f, "{{")?;
+        for (i, (key, value)) in pairs.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}:{}", escape_json_str(key), value)?;
+        }
+        write!(f, "}}")
+      }
+    }
+  }
+}
+
+/// JSONの文字列を読み込む
+///
+/// 読み込めなければ、読み込めなかった位置（バイト単位）を返す。
+pub fn parse(input: &str) -> Result<Json, usize> {
+  let bytes = input.as_bytes();
+  let (value, pos) = parse_value(input, skip_spaces(bytes, 0))?;
+  let pos = skip_spaces(bytes, pos);
+  if pos == bytes.len() {
+    Ok(value)
+  } else {
+    Err(pos)
+  }
+}
+
+fn skip_spaces(bytes: &[u8], mut pos: usize) -> usize {
+  while pos < bytes.len() && b" \t\r\n".contains(&bytes[pos]) {
+    pos += 1;
+  }
+  pos
+}
+
+fn parse_value(input: &str, pos: usize) -> Result<(Json, usize), usize> {
+  let bytes = input.as_bytes();
+  match bytes.get(pos) {
+    Some(b'n') if input[pos..].starts_with("null") => Ok((Json::Null, pos + 4)),
+    Some(b't') if input[pos..].starts_with("true") => Ok((Json::Bool(true), pos + 4)),
+    Some(b'f') if input[pos..].starts_with("false") => Ok((Json::Bool(false), pos + 5)),
+    Some(b'"') => {
+      let (s, pos) = parse_str(input, pos)?;
+      Ok((Json::Str(s), pos))
+    }
+    Some(b'[') => {
+      let mut v = Vec::new();
+      let mut pos = skip_spaces(bytes, pos + 1);
+      if bytes.get(pos) == Some(&b']') {
+        return Ok((Json::Array(v), pos + 1));
+      }
+      loop {
+        let (value, p) = parse_value(input, pos)?;
+        v.push(value);
+        pos = skip_spaces(bytes, p);
+        match bytes.get(pos) {
+          Some(b',') => pos = skip_spaces(bytes, pos + 1),
+          Some(b']') => return Ok((Json::Array(v), pos + 1)),
+          _ => return Err(pos),
+        }
+      }
+    }
+    Some(b'{') => {
+      let mut pairs = Vec::new();
+      let mut pos = skip_spaces(bytes, pos + 1);
+      if bytes.get(pos) == Some(&b'}') {
+        return Ok((Json::Object(pairs), pos + 1));
+      }
+      loop {
+        if bytes.get(pos) != Some(&b'"') {
+          return Err(pos);
+        }
+        let (key, p) = parse_str(input, pos)?;
+        pos = skip_spaces(bytes, p);
+        if bytes.get(pos) != Some(&b':') {
+          return Err(pos);
+        }
+        let (value, p) = parse_value(input, skip_spaces(bytes, pos + 1))?;
+        pairs.push((key, value));
+        pos = skip_spaces(bytes, p);
+        match bytes.get(pos) {
+          Some(b',') => pos = skip_spaces(bytes, pos + 1),
+          Some(b'}') => return Ok((Json::Object(pairs), pos + 1)),
+          _ => return Err(pos),
+        }
+      }
+    }
+    Some(b'-') | Some(b'0'..=b'9') => {
+      let end = pos
+        + input[pos..]
+          .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+          .unwrap_or(input.len() - pos);
+      match input[pos..end].parse::<f64>() {
+        Ok(n) => Ok((Json::Number(n), end)),
+        Err(_) => Err(pos),
+      }
+    }
+    _ => Err(pos),
+  }
+}
+
+// 引用符で始まる文字列を読み込み、エスケープを元に戻す
+fn parse_str(input: &str, pos: usize) -> Result<(String, usize), usize> {
+  let mut s = String::new();
+  let mut chars = input[pos + 1..].char_indices();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '"' => return Ok((s, pos + 1 + i + 1)),
+      '\\' => {
+        let escaped = match chars.next() {
+          Some((_, '"')) => '"',
+          Some((_, '\\')) => '\\',
+          Some((_, '/')) => '/',
+          Some((_, 'b')) => '\u{8}',
+          Some((_, 'f')) => '\u{c}',
+          Some((_, 'n')) => '\n',
+          Some((_, 'r')) => '\r',
+          Some((_, 't')) => '\t',
+          Some((j, 'u')) => {
+            let start = pos + 1 + j + 1;
+            let code = read_hex4(input, start)?;
+            for _ in 0..4 {
+              chars.next();
+            }
+            // サロゲートペアは続く\uXXXXと合わせて1文字にする
+            if (0xD800..0xDC00).contains(&code) && input[start + 4..].starts_with("\\u") {
+              let low = read_hex4(input, start + 6)?;
+              for _ in 0..6 {
+                chars.next();
+              }
+              let c = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+              std::char::from_u32(c).unwrap_or('\u{FFFD}')
+            } else {
+              std::char::from_u32(code).unwrap_or('\u{FFFD}')
+            }
+          }
+          _ => return Err(pos + 1 + i),
+        };
+        s.push(escaped)
+      }
+      c => s.push(c),
+    }
+  }
+  Err(input.len())
+}
+
+fn read_hex4(input: &str, pos: usize) -> Result<u32, usize> {
+  input
+    .get(pos..pos + 4)
+    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    .ok_or(pos)
+}
+
+#[test]
+fn check_parse() {
+  let input = r#"{"id": 1, "params": {"text": "a\"\nあ😀", "list": [true, null, -1.5]}}"#;
+  let value = parse(input).unwrap();
+  assert_eq!(value.get("id").as_usize(), Some(1));
+  assert_eq!(value.get("params").get("text").as_str(), Some("a\"\nあ😀"));
+  assert_eq!(
+    value.get("params").get("list"),
+    &Json::Array(vec![Json::Bool(true), Json::Null, Json::Number(-1.5)])
+  );
+  assert_eq!(parse(&value.to_string()), Ok(value));
+  assert_eq!(parse("{\"a\": }"), Err(6));
+}
@@ -1,103 +1,427 @@
-use clap::{App, Arg};
-use std::fs::File;
-use std::io::prelude::*;
+use clap::{App, Arg, SubCommand};
 use std::path::Path;
 
-pub mod backend;
-pub mod error;
-pub mod frontend;
-pub mod types;
+use llmaker::{backend, error, lsp};
 
-fn print_line() {
-  println!(" --- --- ---");
-}
-
-fn print_msg(s: &str) {
-  println!("  {}", s);
-}
-
-fn write_file(file_name: String, text: String) {
-  let mut file = File::create(file_name).unwrap();
-  file.write_all(text.as_bytes()).unwrap();
-}
-
-fn sub(
-  input_file_name_opt: Option<&str>,
-  output_file_name_opt: Option<&str>,
-) -> Result<(), error::Error> {
-  let mut input_file_name = match input_file_name_opt {
-    Some(s) => Ok(s),
-    None => Err(error::Error::OptionError(
-      error::OptionError::NoInputFileName,
-    )),
-  }?;
-  let output_file_name = match output_file_name_opt {
-    Some(path) => path.to_string(),
-    None => {
-      let path = Path::new(input_file_name);
-      let parent_opt = path.parent();
-      let file_steam_opt = path.file_stem();
-      let new_path = match (parent_opt, file_steam_opt) {
-        (Some(parent), Some(file_steam)) => Ok(parent.join(file_steam)),
-        (None, Some(file_steam)) => Ok((Path::new("")).join(file_steam)),
-        _ => Err(error::Error::OptionError(
-          error::OptionError::BrokenInputFilePath(input_file_name.to_string()),
-        )),
-      }?;
-      format!("{}.rs", new_path.to_str().unwrap().to_string())
-    }
-  };
-  print_line();
-  print_msg(&format!("target file: '{}'", output_file_name));
-  print_line();
-  print_msg(&format!("parsing '{}' ...", input_file_name));
-  let mut f = match File::open(&mut input_file_name) {
-    Ok(v) => Ok(v),
-    Err(_) => Err(error::Error::OptionError(
-      error::OptionError::NotFoundInputFileName(input_file_name.to_string()),
-    )),
-  }?;
-  let mut contents = String::new();
-  match f.read_to_string(&mut contents) {
-    Ok(_) => Ok(()),
-    Err(_) => Err(error::Error::OptionError(
-      error::OptionError::BrokenInputFile(input_file_name.to_string()),
-    )),
-  }?;
-  let ast = frontend::get_ast(&contents)?;
-  print_msg("dune.");
-  print_line();
-  print_msg("making texts ...");
-  let output_str = backend::to_string(ast)?;
-  print_msg("dune.");
-  print_line();
-  write_file(output_file_name.clone(), output_str);
-  print_msg(&format!("output written on '{}'", output_file_name));
-  Ok(())
-}
+mod cli;
 
 fn main() {
   let app = App::new("llmaker")
     .version("0.0.1")
     .arg(
       Arg::with_name("input")
-        .help("Specify input file")
+        .help("Specify input file ('-' for stdin)")
         .value_name("FILE")
-        .takes_value(true),
+        .takes_value(true)
+        .multiple(true),
     )
     .arg(
       Arg::with_name("output")
-        .help("Specify output file")
+        .help("Specify output file ('-' for stdout)")
         .value_name("FILE")
         .short("o")
         .long("output")
         .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("source-map")
+        .help("Write a JSON source map from generated code to the grammar file")
+        .value_name("FILE")
+        .long("source-map")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("verify")
+        .help("Check that the output file is up to date instead of writing it")
+        .long("verify"),
+    )
+    .arg(
+      Arg::with_name("watch")
+        .help("Regenerate the output whenever the input file changes")
+        .long("watch")
+        .conflicts_with("verify"),
+    )
+    .arg(
+      Arg::with_name("emit")
+        .help("Specify what to output")
+        .value_name("KIND")
+        .long("emit")
+        .takes_value(true)
+        .possible_values(&cli::generate::EMIT_NAMES)
+        .default_value("rust"),
+    )
+    .arg(
+      Arg::with_name("out-dir")
+        .help("Process all input files and directories, writing outputs under DIR")
+        .value_name("DIR")
+        .long("out-dir")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("jobs")
+        .help("Number of grammar files processed in parallel (only with multiple inputs or --out-dir)")
+        .value_name("N")
+        .short("j")
+        .long("jobs")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("quiet")
+        .help("Print only errors")
+        .short("q")
+        .long("quiet")
+        .global(true)
+        .conflicts_with("verbose"),
+    )
+    .arg(
+      Arg::with_name("verbose")
+        .help("Print more details such as the number of rules and generated lines")
+        .short("v")
+        .long("verbose")
+        .global(true),
+    )
+    .arg(
+      Arg::with_name("coverage")
+        .help("Count how many times each alternative is chosen in the generated parser")
+        .long("coverage"),
+    )
+    .arg(
+      Arg::with_name("cst")
+        .help("Generate a parser returning a concrete syntax tree instead of running the actions")
+        .long("cst"),
+    )
+    .arg(
+      Arg::with_name("trace")
+        .help("Make the generated parser send enter and exit events of rules and tokens to a ParseTracer")
+        .long("trace"),
+    )
+    .arg(
+      Arg::with_name("trace-analysis")
+        .help("Print to stderr which tokens select each alternative of each rule")
+        .long("trace-analysis")
+        .global(true),
+    )
+    .subcommand(
+      SubCommand::with_name("check")
+        .about("Check a grammar file without writing output")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file ('-' for stdin)")
+            .value_name("FILE")
+            .takes_value(true),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("fmt")
+        .about("Format grammar files in place")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input files or directories ('-' for stdin, written to stdout)")
+            .value_name("FILE")
+            .takes_value(true)
+            .multiple(true),
+        )
+        .arg(
+          Arg::with_name("check")
+            .help("Check that the files are formatted instead of writing them")
+            .long("check"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("test")
+        .about("Run the tests of a grammar without generating Rust code")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file ('-' for stdin)")
+            .value_name("FILE")
+            .takes_value(true),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("repl")
+        .about("Parse sequences of token names typed interactively with a grammar")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file")
+            .value_name("FILE")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("rule")
+            .help("Rule to parse with (the first pub rule by default)")
+            .long("rule")
+            .value_name("NAME")
+            .takes_value(true),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("generate-samples")
+        .about("Print random sequences of token names accepted by a grammar")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file ('-' for stdin)")
+            .value_name("FILE")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("count")
+            .help("Number of sequences (10 by default)")
+            .long("count")
+            .value_name("N")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("max-depth")
+            .help("Maximum depth of the derivation trees (8 by default)")
+            .long("max-depth")
+            .value_name("D")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("seed")
+            .help("Seed of the random numbers (taken from the current time by default)")
+            .long("seed")
+            .value_name("S")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("rule")
+            .help("Rule to generate from (the first pub rule by default)")
+            .long("rule")
+            .value_name("NAME")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("near-miss")
+            .help("Print sequences which are rejected because of a single mutated token")
+            .long("near-miss"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("coverage")
+        .about("Report which alternatives were chosen by a parser generated with --coverage")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file")
+            .value_name("FILE")
+            .required(true),
+        )
+        .arg(
+          Arg::with_name("counts")
+            .help("Files written from coverage_dump() (the counts are added up)")
+            .value_name("COUNTS")
+            .required(true)
+            .multiple(true),
+        )
+        .arg(
+          Arg::with_name("uncovered")
+            .help("Print only the alternatives which were never chosen")
+            .long("uncovered"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("rename")
+        .about("Rename a rule or token everywhere it is referenced")
+        .arg(
+          Arg::with_name("input")
+            .help("Specify input file ('-' for stdin, written to stdout)")
+            .value_name("FILE")
+            .required(true),
+        )
+        .arg(
+          Arg::with_name("old-name")
+            .help("Current name of the rule or token")
+            .value_name("OLD")
+            .required(true),
+        )
+        .arg(
+          Arg::with_name("new-name")
+            .help("New name of the rule or token")
+            .value_name("NEW")
+            .required(true),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("lsp").about("Start a language server for grammar files over stdio"),
     );
   let matches = app.get_matches();
-  let input_file_name_opt = matches.value_of("input");
-  let output_file_name_opt = matches.value_of("output");
-  match sub(input_file_name_opt, output_file_name_opt) {
-    Ok(()) => (),
-    Err(e) => error::print_error_msg(e, input_file_name_opt),
+  if let Some(sub_matches) = matches.subcommand_matches("check") {
+    let verbosity = cli::get_verbosity(sub_matches);
+    let is_trace_analysis = sub_matches.is_present("trace-analysis");
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| {
+        cli::check::check(input_file_name, contents, verbosity, is_trace_analysis)
+      },
+    );
+    return;
+  }
+  if matches.subcommand_matches("lsp").is_some() {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    let code = lsp::run(&mut stdin.lock(), &mut stdout.lock()).unwrap_or(1);
+    std::process::exit(code)
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("test") {
+    let verbosity = cli::get_verbosity(sub_matches);
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| cli::test::test(input_file_name, contents, verbosity),
+    );
+    return;
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("repl") {
+    let verbosity = cli::get_verbosity(sub_matches);
+    let rule_name_opt = sub_matches.value_of("rule");
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| {
+        cli::repl::repl(input_file_name, contents, rule_name_opt, verbosity)
+      },
+    );
+    return;
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("generate-samples") {
+    let verbosity = cli::get_verbosity(sub_matches);
+    let rule_name_opt = sub_matches.value_of("rule");
+    let default_seed = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map_or(0, |d| d.as_nanos() as u64);
+    let options = backend::sample::SampleOptions {
+      count: cli::get_number_option(sub_matches, "count", 10, true) as usize,
+      max_depth: cli::get_number_option(sub_matches, "max-depth", 8, true) as usize,
+      seed: cli::get_number_option(sub_matches, "seed", default_seed, false),
+      is_near_miss: sub_matches.is_present("near-miss"),
+    };
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| {
+        cli::sample::generate_samples(
+          input_file_name,
+          contents,
+          rule_name_opt,
+          &options,
+          verbosity,
+        )
+      },
+    );
+    return;
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("coverage") {
+    let count_file_names: Vec<&str> = sub_matches.values_of("counts").unwrap().collect();
+    let is_uncovered_only = sub_matches.is_present("uncovered");
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| {
+        cli::coverage::coverage(
+          input_file_name,
+          contents,
+          &count_file_names,
+          is_uncovered_only,
+        )
+      },
+    );
+    return;
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("rename") {
+    let verbosity = cli::get_verbosity(sub_matches);
+    let old_name = sub_matches.value_of("old-name").unwrap();
+    let new_name = sub_matches.value_of("new-name").unwrap();
+    cli::run(
+      sub_matches.value_of("input"),
+      |input_file_name, contents| {
+        cli::rename::rename(input_file_name, contents, old_name, new_name, verbosity)
+      },
+    );
+    return;
+  }
+  if let Some(sub_matches) = matches.subcommand_matches("fmt") {
+    let input_file_names: Vec<&str> = match sub_matches.values_of("input") {
+      Some(values) => values.collect(),
+      None => Vec::new(),
+    };
+    cli::fmt::fmt_all(
+      &input_file_names,
+      sub_matches.is_present("check"),
+      cli::get_verbosity(sub_matches),
+    );
+    return;
+  }
+  let verbosity = cli::get_verbosity(&matches);
+  let emit = cli::generate::Emit::from_name(matches.value_of("emit").unwrap_or("rust"));
+  let options = cli::generate::Options {
+    output_file_name_opt: matches.value_of("output"),
+    source_map_file_name_opt: matches.value_of("source-map"),
+    emit,
+    is_verify: matches.is_present("verify"),
+    verbosity,
+    is_trace_analysis: matches.is_present("trace-analysis"),
+    generate_options: backend::GenerateOptions {
+      is_coverage: matches.is_present("coverage"),
+      is_trace: matches.is_present("trace"),
+      is_cst: matches.is_present("cst"),
+    },
+  };
+  for name in ["source-map", "coverage", "trace", "cst"].iter() {
+    if emit != cli::generate::Emit::Rust && matches.is_present(name) {
+      error::print_error_msg(
+        error::Error::OptionError(error::OptionError::NotAllowedWithEmit(
+          format!("--{}", name),
+          emit.name().to_string(),
+        )),
+        None,
+        None,
+      )
+    }
+  }
+  let input_file_names: Vec<&str> = match matches.values_of("input") {
+    Some(values) => values.collect(),
+    None => Vec::new(),
+  };
+  let out_dir_opt = matches.value_of("out-dir");
+  let is_batch = input_file_names.len() > 1
+    || out_dir_opt.is_some()
+    || input_file_names
+      .iter()
+      .any(|input_file_name| Path::new(input_file_name).is_dir());
+  if is_batch {
+    for name in ["output", "source-map", "watch"].iter() {
+      if matches.is_present(name) {
+        error::print_error_msg(
+          error::Error::OptionError(error::OptionError::NotAllowedWithMultipleInputs(format!(
+            "--{}",
+            name
+          ))),
+          None,
+          None,
+        )
+      }
+    }
+    let jobs = cli::get_number_option(&matches, "jobs", 1, true) as usize;
+    cli::batch::batch(&input_file_names, out_dir_opt, jobs, &options);
+    return;
+  }
+  if matches.is_present("jobs") {
+    error::print_error_msg(
+      error::Error::OptionError(error::OptionError::OnlyWithMultipleInputs(
+        "--jobs".to_string(),
+      )),
+      None,
+      None,
+    )
+  }
+  let input_file_name_opt = input_file_names.first().copied();
+  if matches.is_present("watch") {
+    match input_file_name_opt {
+      Some(input_file_name) => cli::watch::watch(input_file_name, &options),
+      None => error::print_error_msg(
+        error::Error::OptionError(error::OptionError::NoInputFileName),
+        None,
+        None,
+      ),
+    }
   }
+  cli::run(input_file_name_opt, |input_file_name, contents| {
+    cli::generate::generate(input_file_name, contents, &options)
+  });
 }
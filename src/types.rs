@@ -34,32 +34,54 @@ pub type Term = (Head, Setting, Bnfs);
 
 pub type Head = Vec<(Range, String)>;
 
-pub type Setting = (String, Vec<(Range, String, TypeStr)>);
+// 型や値を表す文字列と、その文字列リテラルの位置情報
+pub type Setting = (TypeStr, Vec<(Range, String, TypeStr)>);
 
-pub type TypeStr = String;
+pub type TypeStr = (Range, String);
 
 pub type Bnfs = Vec<Bnf>;
 
+// 型を省略した規則は型の文字列が空になり、型と値を作るコードはbackendで生成する
+// そのような規則の選択肢では、アクションの文字列の代わりに`=> Label`で付けたラベル（無ければ空文字列）が入る
 #[derive(Debug, Clone)]
 pub enum Bnf {
   Pub(Range, String, TypeStr, Vec<Code>),
   NonPub(Range, String, TypeStr, Vec<Code>),
 }
 
-pub type Code = (Vec<(String, FnOrToken)>, String);
+pub type Code = (Vec<(String, FnOrToken)>, CodeStr);
+
+pub type CodeStr = (Range, String);
 
 #[derive(Debug, Clone)]
 pub enum FnOrToken {
-  Function(String),
-  Token(String),
+  Function(Range, String),
+  Token(Range, String),
+}
+
+impl FnOrToken {
+  pub fn name(&self) -> &String {
+    match self {
+      FnOrToken::Function(_, s) => s,
+      FnOrToken::Token(_, s) => s,
+    }
+  }
+  pub fn range(&self) -> Range {
+    match self {
+      FnOrToken::Function(rng, _) => *rng,
+      FnOrToken::Token(rng, _) => *rng,
+    }
+  }
 }
+
+// 位置情報は比較に使わない
 impl Ord for FnOrToken {
   fn cmp(&self, other: &Self) -> Ordering {
     match (self, other) {
-      (&FnOrToken::Function(ref s1), &FnOrToken::Function(ref s2)) => s1.cmp(&s2),
-      (&FnOrToken::Token(ref s1), &FnOrToken::Token(ref s2)) => s1.cmp(&s2),
-      (&FnOrToken::Function(_), &FnOrToken::Token(_)) => Ordering::Less,
-      (&FnOrToken::Token(_), &FnOrToken::Function(_)) => Ordering::Greater,
+      (FnOrToken::Function(_, s1), FnOrToken::Function(_, s2)) => s1.cmp(s2),
+      (FnOrToken::Token(_, s1), FnOrToken::Token(_, s2)) => s1.cmp(s2),
+      (FnOrToken::Function(_, _), FnOrToken::Token(_, _)) => Ordering::Less,
+      (FnOrToken::Token(_, _), FnOrToken::Function(_, _)) => Ordering::Greater,
     }
   }
 }
@@ -75,8 +97,8 @@ impl Eq for FnOrToken {}
 impl PartialEq for FnOrToken {
   fn eq(&self, other: &Self) -> bool {
     match (self, other) {
-      (&FnOrToken::Function(ref s1), &FnOrToken::Function(ref s2)) => s1 == s2,
-      (&FnOrToken::Token(ref s1), &FnOrToken::Token(ref s2)) => s1 == s2,
+      (FnOrToken::Function(_, s1), FnOrToken::Function(_, s2)) => s1 == s2,
+      (FnOrToken::Token(_, s1), FnOrToken::Token(_, s2)) => s1 == s2,
       _ => false,
     }
   }